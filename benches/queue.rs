@@ -0,0 +1,59 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use algorithms_in_rust::queue::{ListQueue, Queue, RingQueue, ShuffleQueue};
+
+const SIZES: [usize; 3] = [1_000, 10_000, 50_000];
+const ROUNDS: u32 = 5;
+
+fn fill_and_drain<Q: Queue<usize>>(mut queue: Q, size: usize) -> Duration {
+    let start = Instant::now();
+    for data in 0..size {
+        queue.queue(black_box(data));
+    }
+    while let Some(data) = queue.dequeue() {
+        black_box(data);
+    }
+    start.elapsed()
+}
+
+fn steady_state<Q: Queue<usize>>(mut queue: Q, size: usize) -> Duration {
+    for data in 0..size {
+        queue.queue(data);
+    }
+    let start = Instant::now();
+    for data in 0..size {
+        queue.queue(black_box(data));
+        black_box(queue.dequeue());
+    }
+    start.elapsed()
+}
+
+fn best_of<F: FnMut() -> Duration>(mut run: F) -> Duration {
+    (0..ROUNDS).map(|_| run()).min().unwrap()
+}
+
+fn main() {
+    println!(
+        "{:<14} {:>8} {:>16} {:>16} {:>16}",
+        "benchmark", "size", "ShuffleQueue", "RingQueue", "ListQueue"
+    );
+    for size in SIZES {
+        let shuffle = best_of(|| fill_and_drain(ShuffleQueue::new(), size));
+        let ring = best_of(|| fill_and_drain(RingQueue::new(), size));
+        let list = best_of(|| fill_and_drain(ListQueue::new(), size));
+        println!(
+            "{:<14} {:>8} {:>16?} {:>16?} {:>16?}",
+            "fill_and_drain", size, shuffle, ring, list
+        );
+    }
+    for size in SIZES {
+        let shuffle = best_of(|| steady_state(ShuffleQueue::new(), size));
+        let ring = best_of(|| steady_state(RingQueue::new(), size));
+        let list = best_of(|| steady_state(ListQueue::new(), size));
+        println!(
+            "{:<14} {:>8} {:>16?} {:>16?} {:>16?}",
+            "steady_state", size, shuffle, ring, list
+        );
+    }
+}
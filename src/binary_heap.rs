@@ -1,23 +1,28 @@
+use alloc::vec::Vec;
+
+use crate::error::Error;
+
 pub trait BinaryHeap<T> {
     fn insert(&mut self, data: T, key: usize);
 
-    fn extract(&mut self) -> Result<T, HeapError>;
+    fn extract(&mut self) -> Result<T, Error>;
+
+    fn insert_extract(&mut self, data: T, key: usize) -> Result<T, Error>;
 
-    fn insert_extract(&mut self, data: T, key: usize) -> Result<T, HeapError>;
+    fn search(&self, key: usize) -> Result<T, Error>
+    where
+        T: Clone;
 
-    fn search(&self, key: usize) -> Result<T, HeapError>;
+    /// The element `extract` would return next.
+    fn peek(&self) -> Result<&T, Error>;
+
+    fn peek_mut(&mut self) -> Result<&mut T, Error>;
 
     fn size(&self) -> usize;
 
     fn is_empty(&self) -> bool;
 }
 
-#[derive(Debug)]
-pub enum HeapError {
-    KeyNotFound,
-    HeapEmpty,
-}
-
 struct BinaryHeapNode<T> {
     data: T,
     key: usize,
@@ -93,53 +98,56 @@ impl<T> BinaryMinHeap<T> {
     }
 }
 
-impl<T: Clone> BinaryHeap<T> for BinaryMinHeap<T> {
+impl<T> BinaryHeap<T> for BinaryMinHeap<T> {
     fn insert(&mut self, data: T, key: usize) {
         self.heap.push(BinaryHeapNode { data, key });
         self.size += 1;
         self.up_heap(self.size - 1);
     }
 
-    fn extract(&mut self) -> Result<T, HeapError> {
+    fn extract(&mut self) -> Result<T, Error> {
         if self.size == 0 {
-            return Err(HeapError::HeapEmpty);
+            return Err(Error::Empty);
         }
-        let data = self.heap[0].data.clone();
+        let root_node = self.heap.swap_remove(0);
 
         self.size -= 1;
         if self.size > 0 {
-            self.heap.swap(0, self.size);
             self.down_heap(0);
         }
 
-        Ok(data)
+        Ok(root_node.data)
     }
 
-    fn insert_extract(&mut self, mut data: T, key: usize) -> Result<T, HeapError> {
+    fn insert_extract(&mut self, data: T, key: usize) -> Result<T, Error> {
         if self.size == 0 {
-            return Err(HeapError::HeapEmpty);
+            return Err(Error::Empty);
         }
         if self.heap[0].key < key {
-            data = self.heap[0].data.clone();
+            let root_node = core::mem::replace(&mut self.heap[0], BinaryHeapNode { data, key });
             self.down_heap(0);
+            return Ok(root_node.data);
         }
 
         Ok(data)
     }
 
-    fn search(&self, key: usize) -> Result<T, HeapError> {
+    fn search(&self, key: usize) -> Result<T, Error>
+    where
+        T: Clone,
+    {
         let mut current_index = 0usize;
         let mut left_child_larger = false;
 
         if self.size == 0 {
-            return Err(HeapError::HeapEmpty);
+            return Err(Error::Empty);
         }
         loop {
             if self.heap[current_index].key == key {
                 return Ok(self.heap[current_index].data.clone());
             } else if self.heap[current_index].key > key {
-                if left_child_larger == true {
-                    return Err(HeapError::KeyNotFound);
+                if left_child_larger {
+                    return Err(Error::KeyNotFound);
                 } else {
                     left_child_larger = true;
                     current_index += 1;
@@ -147,12 +155,23 @@ impl<T: Clone> BinaryHeap<T> for BinaryMinHeap<T> {
             } else {
                 current_index += 1;
                 if current_index == self.size {
-                    return Err(HeapError::KeyNotFound);
+                    return Err(Error::KeyNotFound);
                 }
             }
         }
     }
 
+    fn peek(&self) -> Result<&T, Error> {
+        self.heap.first().map(|node| &node.data).ok_or(Error::Empty)
+    }
+
+    fn peek_mut(&mut self) -> Result<&mut T, Error> {
+        self.heap
+            .first_mut()
+            .map(|node| &mut node.data)
+            .ok_or(Error::Empty)
+    }
+
     fn size(&self) -> usize {
         self.size
     }
@@ -232,53 +251,56 @@ impl<T> BinaryMaxHeap<T> {
     }
 }
 
-impl<T: Clone> BinaryHeap<T> for BinaryMaxHeap<T> {
+impl<T> BinaryHeap<T> for BinaryMaxHeap<T> {
     fn insert(&mut self, data: T, key: usize) {
         self.heap.push(BinaryHeapNode { data, key });
         self.size += 1;
         self.up_heap(self.size - 1);
     }
 
-    fn extract(&mut self) -> Result<T, HeapError> {
+    fn extract(&mut self) -> Result<T, Error> {
         if self.size == 0 {
-            return Err(HeapError::KeyNotFound);
+            return Err(Error::Empty);
         }
-        let data = self.heap[0].data.clone();
+        let root_node = self.heap.swap_remove(0);
 
         self.size -= 1;
         if self.size > 0 {
-            self.heap.swap(0, self.size);
             self.down_heap(0);
         }
 
-        Ok(data)
+        Ok(root_node.data)
     }
 
-    fn insert_extract(&mut self, mut data: T, key: usize) -> Result<T, HeapError> {
+    fn insert_extract(&mut self, data: T, key: usize) -> Result<T, Error> {
         if self.size == 0 {
-            return Err(HeapError::HeapEmpty);
+            return Err(Error::Empty);
         }
         if self.heap[0].key > key {
-            data = self.heap[0].data.clone();
+            let root_node = core::mem::replace(&mut self.heap[0], BinaryHeapNode { data, key });
             self.down_heap(0);
+            return Ok(root_node.data);
         }
 
         Ok(data)
     }
 
-    fn search(&self, key: usize) -> Result<T, HeapError> {
+    fn search(&self, key: usize) -> Result<T, Error>
+    where
+        T: Clone,
+    {
         let mut current_index = 0usize;
         let mut left_child_smaller = false;
 
         if self.size == 0 {
-            return Err(HeapError::HeapEmpty);
+            return Err(Error::Empty);
         }
         loop {
             if self.heap[current_index].key == key {
                 return Ok(self.heap[current_index].data.clone());
             } else if self.heap[current_index].key < key {
-                if left_child_smaller == true {
-                    return Err(HeapError::KeyNotFound);
+                if left_child_smaller {
+                    return Err(Error::KeyNotFound);
                 } else {
                     left_child_smaller = true;
                     current_index += 1;
@@ -286,12 +308,23 @@ impl<T: Clone> BinaryHeap<T> for BinaryMaxHeap<T> {
             } else {
                 current_index += 1;
                 if current_index == self.size {
-                    return Err(HeapError::KeyNotFound);
+                    return Err(Error::KeyNotFound);
                 }
             }
         }
     }
 
+    fn peek(&self) -> Result<&T, Error> {
+        self.heap.first().map(|node| &node.data).ok_or(Error::Empty)
+    }
+
+    fn peek_mut(&mut self) -> Result<&mut T, Error> {
+        self.heap
+            .first_mut()
+            .map(|node| &mut node.data)
+            .ok_or(Error::Empty)
+    }
+
     fn size(&self) -> usize {
         self.size
     }
@@ -312,11 +345,11 @@ mod tests {
         let (test_key_1, test_key_2, test_key_3) = (10240000, 512, 1);
 
         assert!(test_heap.size() == 0);
-        test_heap.insert(test_data_1.clone(), test_key_1);
+        test_heap.insert(test_data_1, test_key_1);
         assert!(test_heap.size() == 1);
-        test_heap.insert(test_data_2.clone(), test_key_2);
+        test_heap.insert(test_data_2, test_key_2);
         assert!(test_heap.size() == 2);
-        test_heap.insert(test_data_3.clone(), test_key_3);
+        test_heap.insert(test_data_3, test_key_3);
         assert!(test_heap.size() == 3);
     }
 
@@ -327,11 +360,11 @@ mod tests {
         let (test_key_1, test_key_2, test_key_3) = (10240000, 512, 1);
 
         assert!(test_heap.size() == 0);
-        test_heap.insert(test_data_1.clone(), test_key_1);
+        test_heap.insert(test_data_1, test_key_1);
         assert!(test_heap.size() == 1);
-        test_heap.insert(test_data_2.clone(), test_key_2);
+        test_heap.insert(test_data_2, test_key_2);
         assert!(test_heap.size() == 2);
-        test_heap.insert(test_data_3.clone(), test_key_3);
+        test_heap.insert(test_data_3, test_key_3);
         assert!(test_heap.size() == 3);
     }
 
@@ -341,11 +374,11 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3, test_data_4, test_data_5) = (1, 2, 3, 4, 2);
         let (test_key_1, test_key_2, test_key_3, test_key_4, test_key_5) = (1, 2, 3, 400, 2);
 
-        test_heap.insert(test_data_2.clone(), test_key_2);
-        test_heap.insert(test_data_4.clone(), test_key_4);
-        test_heap.insert(test_data_5.clone(), test_key_5);
-        test_heap.insert(test_data_1.clone(), test_key_1);
-        test_heap.insert(test_data_3.clone(), test_key_3);
+        test_heap.insert(test_data_2, test_key_2);
+        test_heap.insert(test_data_4, test_key_4);
+        test_heap.insert(test_data_5, test_key_5);
+        test_heap.insert(test_data_1, test_key_1);
+        test_heap.insert(test_data_3, test_key_3);
 
         assert!(test_heap.size() == 5);
         assert!(test_heap.extract().unwrap() == test_data_1);
@@ -367,11 +400,11 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3, test_data_4, test_data_5) = (1, 2, 3, 4, 3);
         let (test_key_1, test_key_2, test_key_3, test_key_4, test_key_5) = (1, 2, 3, 400, 3);
 
-        test_heap.insert(test_data_3.clone(), test_key_3);
-        test_heap.insert(test_data_1.clone(), test_key_1);
-        test_heap.insert(test_data_5.clone(), test_key_5);
-        test_heap.insert(test_data_4.clone(), test_key_4);
-        test_heap.insert(test_data_2.clone(), test_key_2);
+        test_heap.insert(test_data_3, test_key_3);
+        test_heap.insert(test_data_1, test_key_1);
+        test_heap.insert(test_data_5, test_key_5);
+        test_heap.insert(test_data_4, test_key_4);
+        test_heap.insert(test_data_2, test_key_2);
 
         assert!(test_heap.size() == 5);
         assert!(test_heap.extract().unwrap() == test_data_4);
@@ -393,10 +426,10 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3, test_data_4, test_data_5) = (1, 2, 3, 4, 2);
         let (test_key_1, test_key_2, test_key_3, test_key_4, test_key_5) = (1, 2, 3, 400, 2);
 
-        test_heap.insert(test_data_2.clone(), test_key_2);
-        test_heap.insert(test_data_4.clone(), test_key_4);
-        test_heap.insert(test_data_5.clone(), test_key_5);
-        test_heap.insert(test_data_1.clone(), test_key_1);
+        test_heap.insert(test_data_2, test_key_2);
+        test_heap.insert(test_data_4, test_key_4);
+        test_heap.insert(test_data_5, test_key_5);
+        test_heap.insert(test_data_1, test_key_1);
 
         assert!(test_heap.size() == 4);
         assert!(test_heap.insert_extract(test_data_3, test_key_3).unwrap() == test_data_1);
@@ -409,10 +442,10 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3, test_data_4, test_data_5) = (1, 2, 3, 4, 3);
         let (test_key_1, test_key_2, test_key_3, test_key_4, test_key_5) = (1, 2, 3, 400, 3);
 
-        test_heap.insert(test_data_3.clone(), test_key_3);
-        test_heap.insert(test_data_1.clone(), test_key_1);
-        test_heap.insert(test_data_5.clone(), test_key_5);
-        test_heap.insert(test_data_4.clone(), test_key_4);
+        test_heap.insert(test_data_3, test_key_3);
+        test_heap.insert(test_data_1, test_key_1);
+        test_heap.insert(test_data_5, test_key_5);
+        test_heap.insert(test_data_4, test_key_4);
 
         assert!(test_heap.size() == 4);
         assert!(test_heap.insert_extract(test_data_2, test_key_2).unwrap() == test_data_4);
@@ -429,12 +462,12 @@ mod tests {
         assert!(test_min_heap.search(test_key_1).is_err());
         assert!(test_max_heap.search(test_key_1).is_err());
 
-        test_min_heap.insert(test_data_3.clone(), test_key_3);
-        test_min_heap.insert(test_data_2.clone(), test_key_2);
-        test_min_heap.insert(test_data_1.clone(), test_key_1);
-        test_max_heap.insert(test_data_1.clone(), test_key_1);
-        test_max_heap.insert(test_data_2.clone(), test_key_2);
-        test_max_heap.insert(test_data_3.clone(), test_key_3);
+        test_min_heap.insert(test_data_3, test_key_3);
+        test_min_heap.insert(test_data_2, test_key_2);
+        test_min_heap.insert(test_data_1, test_key_1);
+        test_max_heap.insert(test_data_1, test_key_1);
+        test_max_heap.insert(test_data_2, test_key_2);
+        test_max_heap.insert(test_data_3, test_key_3);
 
         assert!(test_min_heap.size() == 3);
         assert!(test_min_heap.search(test_key_1).unwrap() == test_data_1);
@@ -460,24 +493,108 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3) = (1, 512, 10240000);
         let (test_key_1, test_key_2, test_key_3) = (10240000, 512, 1);
 
-        assert!(test_min_heap.is_empty() == true);
-        test_min_heap.insert(test_data_1.clone(), test_key_3);
-        test_min_heap.insert(test_data_2.clone(), test_key_2);
-        test_min_heap.insert(test_data_3.clone(), test_key_1);
-        assert!(test_min_heap.is_empty() == false);
+        assert!(test_min_heap.is_empty());
+        test_min_heap.insert(test_data_1, test_key_3);
+        test_min_heap.insert(test_data_2, test_key_2);
+        test_min_heap.insert(test_data_3, test_key_1);
+        assert!(!test_min_heap.is_empty());
         let _ = test_min_heap.extract();
         let _ = test_min_heap.extract();
         let _ = test_min_heap.extract();
-        assert!(test_max_heap.is_empty() == true);
+        assert!(test_max_heap.is_empty());
 
-        assert!(test_max_heap.is_empty() == true);
-        let _ = test_max_heap.insert(test_data_1.clone(), test_key_3);
-        let _ = test_max_heap.insert(test_data_2.clone(), test_key_2);
-        let _ = test_max_heap.insert(test_data_3.clone(), test_key_1);
-        assert!(test_max_heap.is_empty() == false);
+        assert!(test_max_heap.is_empty());
+        test_max_heap.insert(test_data_1, test_key_3);
+        test_max_heap.insert(test_data_2, test_key_2);
+        test_max_heap.insert(test_data_3, test_key_1);
+        assert!(!test_max_heap.is_empty());
         let _ = test_max_heap.extract();
         let _ = test_max_heap.extract();
         let _ = test_max_heap.extract();
-        assert!(test_max_heap.is_empty() == true);
+        assert!(test_max_heap.is_empty());
+    }
+
+    #[test]
+    fn heap_error_kinds() {
+        let mut test_min_heap = BinaryMinHeap::new(None);
+        let mut test_max_heap = BinaryMaxHeap::new(None);
+
+        assert!(test_min_heap.extract() == Err(Error::Empty));
+        assert!(test_max_heap.extract() == Err(Error::Empty));
+        assert!(test_min_heap.search(1) == Err(Error::Empty));
+        assert!(test_max_heap.search(1) == Err(Error::Empty));
+
+        test_min_heap.insert(1, 1);
+        test_max_heap.insert(1, 1);
+        assert!(test_min_heap.search(2) == Err(Error::KeyNotFound));
+        assert!(test_max_heap.search(0) == Err(Error::KeyNotFound));
+    }
+
+    #[test]
+    fn heap_extract_then_insert() {
+        let mut test_min_heap = BinaryMinHeap::new(None);
+        let mut test_max_heap = BinaryMaxHeap::new(None);
+
+        for key in [5, 3, 8, 1] {
+            test_min_heap.insert(key * 10, key);
+            test_max_heap.insert(key * 10, key);
+        }
+        assert!(test_min_heap.extract() == Ok(10));
+        assert!(test_max_heap.extract() == Ok(80));
+        test_min_heap.insert(20, 2);
+        test_max_heap.insert(70, 7);
+        assert!(test_min_heap.peek() == Ok(&20));
+        assert!(test_max_heap.peek() == Ok(&70));
+
+        assert!(test_min_heap.insert_extract(40, 4) == Ok(20));
+        assert!(test_min_heap.insert_extract(0, 0) == Ok(0));
+        assert!(test_max_heap.insert_extract(60, 6) == Ok(70));
+        *test_max_heap.peek_mut().unwrap() += 1;
+
+        let min_order: Vec<_> = core::iter::from_fn(|| test_min_heap.extract().ok()).collect();
+        let max_order: Vec<_> = core::iter::from_fn(|| test_max_heap.extract().ok()).collect();
+        assert!(min_order == vec![30, 40, 50, 80]);
+        assert!(max_order == vec![61, 50, 30, 10]);
+        assert!(test_min_heap.peek() == Err(Error::Empty));
+    }
+
+    #[test]
+    fn heap_holds_non_clone_data() {
+        let mut test_heap: BinaryMinHeap<Box<dyn Fn(i32) -> i32>> = BinaryMinHeap::new(None);
+
+        test_heap.insert(Box::new(|x| x * 2), 2);
+        test_heap.insert(Box::new(|x| x + 1), 1);
+
+        assert!(test_heap.peek().unwrap()(10) == 11);
+        assert!(test_heap.extract().unwrap()(10) == 11);
+        assert!(test_heap.extract().unwrap()(10) == 20);
+    }
+
+    #[test]
+    fn heap_extract_and_insert_extract_keep_order() {
+        let mut test_min_heap = BinaryMinHeap::new(None);
+        let mut test_max_heap = BinaryMaxHeap::new(None);
+
+        for key in [5, 3, 8, 1] {
+            test_min_heap.insert(key * 10, key);
+            test_max_heap.insert(key * 10, key);
+        }
+        assert!(test_min_heap.extract().unwrap() == 10);
+        assert!(test_max_heap.extract().unwrap() == 80);
+        test_min_heap.insert(20, 2);
+        test_max_heap.insert(70, 7);
+        assert!(test_min_heap.insert_extract(40, 4).unwrap() == 20);
+        assert!(test_max_heap.insert_extract(60, 6).unwrap() == 70);
+
+        let mut min_order = Vec::new();
+        while let Ok(data) = test_min_heap.extract() {
+            min_order.push(data);
+        }
+        let mut max_order = Vec::new();
+        while let Ok(data) = test_max_heap.extract() {
+            max_order.push(data);
+        }
+        assert!(min_order == vec![30, 40, 50, 80]);
+        assert!(max_order == vec![60, 50, 30, 10]);
     }
 }
@@ -0,0 +1,399 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::stack::{Stack, UnboundedStack, VecStack};
+
+pub struct BracketConfig {
+    pairs: Vec<(char, char)>,
+    quotes: Vec<char>,
+    line_comments: Vec<String>,
+    block_comments: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketError {
+    Unexpected {
+        position: usize,
+        found: char,
+    },
+    Mismatched {
+        open_position: usize,
+        close_position: usize,
+        expected: char,
+        found: char,
+    },
+    Unclosed {
+        position: usize,
+        open: char,
+    },
+    UnterminatedString {
+        position: usize,
+    },
+    UnterminatedComment {
+        position: usize,
+    },
+}
+
+pub struct BracketMatches {
+    by_open: Vec<(usize, usize)>,
+    by_close: Vec<(usize, usize)>,
+}
+
+impl BracketConfig {
+    /// A config with only the given bracket pairs and no quotes or comments.
+    /// A pair such as `('|', '|')` whose opener is also its closer closes the
+    /// innermost open bracket if that is its own opener, and opens otherwise.
+    pub fn new(pairs: &[(char, char)]) -> Self {
+        BracketConfig {
+            pairs: pairs.to_vec(),
+            quotes: Vec::new(),
+            line_comments: Vec::new(),
+            block_comments: Vec::new(),
+        }
+    }
+
+    /// Text between two unescaped `quote` characters is skipped. A backslash
+    /// escapes the character after it.
+    pub fn add_quote(&mut self, quote: char) {
+        self.quotes.push(quote);
+    }
+
+    /// Panics if `start` is empty.
+    pub fn add_line_comment(&mut self, start: &str) {
+        assert!(!start.is_empty(), "a comment delimiter can not be empty");
+        self.line_comments.push(start.to_string());
+    }
+
+    /// Panics if `start` or `end` is empty.
+    pub fn add_block_comment(&mut self, start: &str, end: &str) {
+        assert!(
+            !start.is_empty() && !end.is_empty(),
+            "a comment delimiter can not be empty"
+        );
+        self.block_comments
+            .push((start.to_string(), end.to_string()));
+    }
+
+    fn closing_for(&self, character: char) -> Option<char> {
+        self.pairs
+            .iter()
+            .find(|(open, _)| *open == character)
+            .map(|(_, close)| *close)
+    }
+
+    fn is_closing(&self, character: char) -> bool {
+        self.pairs.iter().any(|(_, close)| *close == character)
+    }
+}
+
+impl Default for BracketConfig {
+    /// `()`, `[]` and `{}` with C-style strings and comments.
+    fn default() -> Self {
+        let mut config = BracketConfig::new(&[('(', ')'), ('[', ']'), ('{', '}')]);
+        config.add_quote('"');
+        config.add_quote('\'');
+        config.add_line_comment("//");
+        config.add_block_comment("/*", "*/");
+        config
+    }
+}
+
+impl fmt::Display for BracketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BracketError::Unexpected { position, found } => {
+                write!(f, "unexpected '{}' at position {}", found, position)
+            }
+            BracketError::Mismatched {
+                open_position,
+                close_position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected '{}' at position {} to close position {}, found '{}'",
+                expected, close_position, open_position, found
+            ),
+            BracketError::Unclosed { position, open } => {
+                write!(f, "unclosed '{}' at position {}", open, position)
+            }
+            BracketError::UnterminatedString { position } => {
+                write!(f, "unterminated string starting at position {}", position)
+            }
+            BracketError::UnterminatedComment { position } => {
+                write!(f, "unterminated comment starting at position {}", position)
+            }
+        }
+    }
+}
+
+impl core::error::Error for BracketError {}
+
+impl BracketMatches {
+    /// Every matched pair as `(open_position, close_position)`, ordered by
+    /// opening position.
+    pub fn pairs(&self) -> &[(usize, usize)] {
+        &self.by_open
+    }
+
+    /// The position of the bracket paired with the one at `position`.
+    pub fn matching(&self, position: usize) -> Option<usize> {
+        if let Ok(index) = self
+            .by_open
+            .binary_search_by_key(&position, |(open, _)| *open)
+        {
+            return Some(self.by_open[index].1);
+        }
+        self.by_close
+            .binary_search_by_key(&position, |(_, close)| *close)
+            .ok()
+            .map(|index| self.by_close[index].0)
+    }
+}
+
+/// Pairs every bracket in `source`, skipping quoted strings and comments.
+/// Positions are byte offsets into `source`.
+pub fn match_brackets(
+    source: &str,
+    config: &BracketConfig,
+) -> Result<BracketMatches, BracketError> {
+    let mut open_brackets = VecStack::new();
+    let mut by_close = Vec::new();
+    let mut position = 0;
+
+    while let Some(character) = source[position..].chars().next() {
+        let rest = &source[position..];
+        if let Some((start, end)) = config
+            .block_comments
+            .iter()
+            .find(|(start, _)| rest.starts_with(start.as_str()))
+        {
+            let body_start = position + start.len();
+            position = source[body_start..]
+                .find(end.as_str())
+                .map(|offset| body_start + offset + end.len())
+                .ok_or(BracketError::UnterminatedComment { position })?;
+            continue;
+        }
+        if config
+            .line_comments
+            .iter()
+            .any(|start| rest.starts_with(start.as_str()))
+        {
+            position = rest
+                .find('\n')
+                .map_or(source.len(), |offset| position + offset + 1);
+            continue;
+        }
+        if config.quotes.contains(&character) {
+            position = skip_quoted(source, position, character)?;
+            continue;
+        }
+
+        let closes_innermost = open_brackets
+            .peek()
+            .is_some_and(|&(_, expected, _)| expected == character);
+        if let Some(close) = config.closing_for(character).filter(|_| !closes_innermost) {
+            open_brackets.push_unbounded((character, close, position));
+        } else if config.is_closing(character) {
+            match open_brackets.pop() {
+                Some((_, expected, open_position)) if expected == character => {
+                    by_close.push((open_position, position));
+                }
+                Some((_, expected, open_position)) => {
+                    return Err(BracketError::Mismatched {
+                        open_position,
+                        close_position: position,
+                        expected,
+                        found: character,
+                    })
+                }
+                None => {
+                    return Err(BracketError::Unexpected {
+                        position,
+                        found: character,
+                    })
+                }
+            }
+        }
+        position += character.len_utf8();
+    }
+
+    if let Some((open, _, position)) = open_brackets.pop() {
+        return Err(BracketError::Unclosed { position, open });
+    }
+    let mut by_open = by_close.clone();
+    by_open.sort_unstable();
+    Ok(BracketMatches { by_open, by_close })
+}
+
+pub fn check_brackets(source: &str, config: &BracketConfig) -> Result<(), BracketError> {
+    match_brackets(source, config).map(|_| ())
+}
+
+fn skip_quoted(source: &str, start: usize, quote: char) -> Result<usize, BracketError> {
+    let mut characters = source[start + quote.len_utf8()..].char_indices();
+    while let Some((offset, character)) = characters.next() {
+        if character == '\\' {
+            characters.next();
+        } else if character == quote {
+            return Ok(start + quote.len_utf8() + offset + quote.len_utf8());
+        }
+    }
+    Err(BracketError::UnterminatedString { position: start })
+}
+
+/// The opening bracket positions that enclose `position`, innermost last.
+pub fn enclosing_brackets(
+    source: &str,
+    config: &BracketConfig,
+    position: usize,
+) -> Result<Vec<usize>, BracketError> {
+    let matches = match_brackets(source, config)?;
+    let mut enclosing = Vec::new();
+    for &(open, close) in matches.pairs() {
+        if open < position && position < close {
+            enclosing.push(open);
+        }
+    }
+    Ok(enclosing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brackets_balanced() {
+        let test_config = BracketConfig::default();
+
+        assert!(check_brackets("", &test_config).is_ok());
+        assert!(check_brackets("fn main() { let a = [1, (2)]; }", &test_config).is_ok());
+        assert!(check_brackets("{[()()]}", &test_config).is_ok());
+    }
+
+    #[test]
+    fn brackets_error_positions() {
+        let test_config = BracketConfig::default();
+
+        assert!(
+            check_brackets("(]", &test_config)
+                == Err(BracketError::Mismatched {
+                    open_position: 0,
+                    close_position: 1,
+                    expected: ')',
+                    found: ']'
+                })
+        );
+        assert!(
+            check_brackets("a)", &test_config)
+                == Err(BracketError::Unexpected {
+                    position: 1,
+                    found: ')'
+                })
+        );
+        assert!(
+            check_brackets("{ ( }", &test_config)
+                == Err(BracketError::Mismatched {
+                    open_position: 2,
+                    close_position: 4,
+                    expected: ')',
+                    found: '}'
+                })
+        );
+        assert!(
+            check_brackets("[ ( ) ", &test_config)
+                == Err(BracketError::Unclosed {
+                    position: 0,
+                    open: '['
+                })
+        );
+        assert!(
+            check_brackets("é(", &test_config)
+                == Err(BracketError::Unclosed {
+                    position: 2,
+                    open: '('
+                })
+        );
+    }
+
+    #[test]
+    fn brackets_skip_strings_and_comments() {
+        let test_config = BracketConfig::default();
+
+        assert!(check_brackets("f(\")\")", &test_config).is_ok());
+        assert!(check_brackets("f('\\'', \"\\\"(\")", &test_config).is_ok());
+        assert!(check_brackets("f() // )\ng()", &test_config).is_ok());
+        assert!(check_brackets("f(/* ) */)", &test_config).is_ok());
+        assert!(
+            check_brackets("f(\"abc)", &test_config)
+                == Err(BracketError::UnterminatedString { position: 2 })
+        );
+        assert!(
+            check_brackets("f( /* )", &test_config)
+                == Err(BracketError::UnterminatedComment { position: 3 })
+        );
+    }
+
+    #[test]
+    fn brackets_custom_pairs() {
+        let mut test_config = BracketConfig::new(&[('<', '>'), ('(', ')')]);
+        test_config.add_line_comment("#");
+
+        assert!(check_brackets("<a<b>> # >", &test_config).is_ok());
+        assert!(check_brackets("[<a>", &test_config).is_ok());
+        assert!(check_brackets("\"<\"", &test_config).is_err());
+    }
+
+    #[test]
+    fn brackets_symmetric_pairs() {
+        let test_config = BracketConfig::new(&[('|', '|'), ('(', ')')]);
+        let test_matches = match_brackets("|a (|b|) |", &test_config).unwrap();
+
+        assert!(test_matches.pairs() == [(0, 9), (3, 7), (4, 6)]);
+        assert!(check_brackets("||||", &test_config).is_ok());
+        assert!(
+            check_brackets("(|)", &test_config)
+                == Err(BracketError::Mismatched {
+                    open_position: 1,
+                    close_position: 2,
+                    expected: '|',
+                    found: ')'
+                })
+        );
+        assert!(
+            check_brackets("|(|", &test_config)
+                == Err(BracketError::Unclosed {
+                    position: 2,
+                    open: '|'
+                })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn brackets_reject_empty_line_comment() {
+        BracketConfig::new(&[('(', ')')]).add_line_comment("");
+    }
+
+    #[test]
+    #[should_panic]
+    fn brackets_reject_empty_block_comment() {
+        BracketConfig::new(&[('(', ')')]).add_block_comment("", "");
+    }
+
+    #[test]
+    fn brackets_matching_pairs() {
+        let test_config = BracketConfig::default();
+        let test_matches = match_brackets("a(b[c]{d})e()", &test_config).unwrap();
+
+        assert!(test_matches.pairs() == [(1, 9), (3, 5), (6, 8), (11, 12)]);
+        assert!(test_matches.matching(1) == Some(9));
+        assert!(test_matches.matching(9) == Some(1));
+        assert!(test_matches.matching(6) == Some(8));
+        assert!(test_matches.matching(12) == Some(11));
+        assert!(test_matches.matching(0).is_none());
+        assert!(enclosing_brackets("a(b[c]{d})e()", &test_config, 4) == Ok(vec![1, 3]));
+    }
+}
@@ -0,0 +1,47 @@
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Empty,
+    KeyNotFound,
+    CapacityExceeded,
+    InvalidHandle,
+    Closed,
+    Timeout,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "collection is empty"),
+            Error::KeyNotFound => write!(f, "key not found"),
+            Error::CapacityExceeded => write!(f, "capacity exceeded"),
+            Error::InvalidHandle => write!(f, "invalid handle"),
+            Error::Closed => write!(f, "collection is closed"),
+            Error::Timeout => write!(f, "operation timed out"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_display() {
+        assert!(Error::Empty.to_string() == "collection is empty");
+        assert!(Error::KeyNotFound.to_string() == "key not found");
+        assert!(Error::CapacityExceeded.to_string() == "capacity exceeded");
+        assert!(Error::InvalidHandle.to_string() == "invalid handle");
+        assert!(Error::Closed.to_string() == "collection is closed");
+        assert!(Error::Timeout.to_string() == "operation timed out");
+    }
+
+    #[test]
+    fn error_is_std_error() {
+        let boxed: Box<dyn std::error::Error> = Box::new(Error::Empty);
+        assert!(boxed.to_string() == "collection is empty");
+    }
+}
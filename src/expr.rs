@@ -0,0 +1,807 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::stack::{Stack, UnboundedStack, VecStack};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprError {
+    pub kind: ExprErrorKind,
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprErrorKind {
+    UnexpectedCharacter(char),
+    InvalidNumber,
+    ExpectedOperand,
+    ExpectedOperator,
+    UnexpectedComma,
+    UnmatchedParenthesis,
+    UnknownVariable(String),
+    UnknownFunction(String),
+    WrongArgumentCount { expected: usize, found: usize },
+    TypeMismatch,
+    DivisionByZero,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+    Negate,
+    Not,
+}
+
+/// Operator symbols with their precedence and associativity, used by
+/// `Expression::parse_with`. `new` gives the standard table, which
+/// `set_binary` and `set_unary` can extend with new symbols or override.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorTable {
+    binary: Vec<(String, Operator, u8, Associativity)>,
+    unary: Vec<(String, Operator, u8)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind<'a> {
+    Number(f64),
+    Bool(bool),
+    Identifier(String),
+    Operator(&'a str),
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token<'a> {
+    kind: TokenKind<'a>,
+    position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RpnToken {
+    Value(Value),
+    Variable(String),
+    Operator(Operator),
+    Function(String, usize),
+}
+
+enum OperatorStackEntry {
+    Operator(Operator, u8, usize),
+    LeftParenthesis(usize),
+    Function(String, usize),
+}
+
+pub struct Expression {
+    rpn: Vec<(RpnToken, usize)>,
+}
+
+type Function = Box<dyn Fn(&[Value]) -> Result<Value, ExprErrorKind>>;
+
+pub struct Context {
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, (Option<usize>, Function)>,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Bool(boolean) => write!(f, "{}", boolean),
+        }
+    }
+}
+
+impl fmt::Display for ExprErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprErrorKind::UnexpectedCharacter(character) => {
+                write!(f, "unexpected character '{}'", character)
+            }
+            ExprErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ExprErrorKind::ExpectedOperand => write!(f, "expected operand"),
+            ExprErrorKind::ExpectedOperator => write!(f, "expected operator"),
+            ExprErrorKind::UnexpectedComma => write!(f, "comma outside of function call"),
+            ExprErrorKind::UnmatchedParenthesis => write!(f, "unmatched parenthesis"),
+            ExprErrorKind::UnknownVariable(name) => write!(f, "unknown variable '{}'", name),
+            ExprErrorKind::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            ExprErrorKind::WrongArgumentCount { expected, found } => {
+                write!(f, "expected {} arguments, found {}", expected, found)
+            }
+            ExprErrorKind::TypeMismatch => write!(f, "type mismatch"),
+            ExprErrorKind::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+impl ExprError {
+    fn new(kind: ExprErrorKind, position: usize) -> Self {
+        ExprError { kind, position }
+    }
+}
+
+impl OperatorTable {
+    /// The standard operators.
+    pub fn new() -> Self {
+        let mut table = OperatorTable {
+            binary: Vec::new(),
+            unary: Vec::new(),
+        };
+        for (symbol, operator, precedence, associativity) in [
+            ("||", Operator::Or, 1, Associativity::Left),
+            ("&&", Operator::And, 2, Associativity::Left),
+            ("==", Operator::Equal, 3, Associativity::Left),
+            ("!=", Operator::NotEqual, 3, Associativity::Left),
+            ("<", Operator::Less, 4, Associativity::Left),
+            ("<=", Operator::LessEqual, 4, Associativity::Left),
+            (">", Operator::Greater, 4, Associativity::Left),
+            (">=", Operator::GreaterEqual, 4, Associativity::Left),
+            ("+", Operator::Add, 5, Associativity::Left),
+            ("-", Operator::Subtract, 5, Associativity::Left),
+            ("*", Operator::Multiply, 6, Associativity::Left),
+            ("/", Operator::Divide, 6, Associativity::Left),
+            ("%", Operator::Remainder, 6, Associativity::Left),
+            ("^", Operator::Power, 8, Associativity::Right),
+        ] {
+            table.set_binary(symbol, operator, precedence, associativity);
+        }
+        table.set_unary("-", Operator::Negate, 7);
+        table.set_unary("!", Operator::Not, 7);
+        table
+    }
+
+    /// Binds `symbol` to a binary operator, replacing any binary operator it
+    /// was bound to. Panics if `symbol` is empty or `operator` is unary.
+    pub fn set_binary(
+        &mut self,
+        symbol: &str,
+        operator: Operator,
+        precedence: u8,
+        associativity: Associativity,
+    ) {
+        assert!(!symbol.is_empty(), "an operator symbol can not be empty");
+        assert!(
+            !operator.is_unary(),
+            "{:?} is not a binary operator",
+            operator
+        );
+        self.binary
+            .retain(|(table_symbol, _, _, _)| table_symbol != symbol);
+        self.binary
+            .push((symbol.to_string(), operator, precedence, associativity));
+    }
+
+    /// Binds `symbol` to a prefix operator, replacing any prefix operator it
+    /// was bound to. Panics if `symbol` is empty or `operator` is binary.
+    pub fn set_unary(&mut self, symbol: &str, operator: Operator, precedence: u8) {
+        assert!(!symbol.is_empty(), "an operator symbol can not be empty");
+        assert!(
+            operator.is_unary(),
+            "{:?} is not a unary operator",
+            operator
+        );
+        self.unary
+            .retain(|(table_symbol, _, _)| table_symbol != symbol);
+        self.unary.push((symbol.to_string(), operator, precedence));
+    }
+
+    fn find_binary(&self, symbol: &str) -> Option<(Operator, u8, Associativity)> {
+        self.binary
+            .iter()
+            .find(|(table_symbol, _, _, _)| table_symbol == symbol)
+            .map(|(_, operator, precedence, associativity)| {
+                (*operator, *precedence, *associativity)
+            })
+    }
+
+    fn find_unary(&self, symbol: &str) -> Option<(Operator, u8)> {
+        self.unary
+            .iter()
+            .find(|(table_symbol, _, _)| table_symbol == symbol)
+            .map(|(_, operator, precedence)| (*operator, *precedence))
+    }
+
+    // Longest match first, so `<=` is never read as `<` followed by `=`.
+    fn longest_symbol(&self, source: &str) -> Option<usize> {
+        self.binary
+            .iter()
+            .map(|(symbol, _, _, _)| symbol)
+            .chain(self.unary.iter().map(|(symbol, _, _)| symbol))
+            .filter(|symbol| source.starts_with(symbol.as_str()))
+            .map(|symbol| symbol.len())
+            .max()
+    }
+}
+
+impl Default for OperatorTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Or => "||",
+            Operator::And => "&&",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+            Operator::Power => "^",
+            Operator::Negate => "neg",
+            Operator::Not => "!",
+        }
+    }
+
+    fn is_unary(self) -> bool {
+        matches!(self, Operator::Negate | Operator::Not)
+    }
+
+    fn apply(self, left: Value, right: Value) -> Result<Value, ExprErrorKind> {
+        use Value::{Bool, Number};
+        Ok(match (self, left, right) {
+            (Operator::Or, Bool(a), Bool(b)) => Bool(a || b),
+            (Operator::And, Bool(a), Bool(b)) => Bool(a && b),
+            (Operator::Equal, a, b) if same_type(a, b) => Bool(a == b),
+            (Operator::NotEqual, a, b) if same_type(a, b) => Bool(a != b),
+            (Operator::Less, Number(a), Number(b)) => Bool(a < b),
+            (Operator::LessEqual, Number(a), Number(b)) => Bool(a <= b),
+            (Operator::Greater, Number(a), Number(b)) => Bool(a > b),
+            (Operator::GreaterEqual, Number(a), Number(b)) => Bool(a >= b),
+            (Operator::Add, Number(a), Number(b)) => Number(a + b),
+            (Operator::Subtract, Number(a), Number(b)) => Number(a - b),
+            (Operator::Multiply, Number(a), Number(b)) => Number(a * b),
+            (Operator::Divide | Operator::Remainder, Number(_), Number(0.0)) => {
+                return Err(ExprErrorKind::DivisionByZero)
+            }
+            (Operator::Divide, Number(a), Number(b)) => Number(a / b),
+            (Operator::Remainder, Number(a), Number(b)) => Number(a % b),
+            (Operator::Power, Number(a), Number(b)) => Number(a.powf(b)),
+            _ => return Err(ExprErrorKind::TypeMismatch),
+        })
+    }
+
+    fn apply_unary(self, operand: Value) -> Result<Value, ExprErrorKind> {
+        match (self, operand) {
+            (Operator::Negate, Value::Number(a)) => Ok(Value::Number(-a)),
+            (Operator::Not, Value::Bool(a)) => Ok(Value::Bool(!a)),
+            _ => Err(ExprErrorKind::TypeMismatch),
+        }
+    }
+}
+
+fn same_type(left: Value, right: Value) -> bool {
+    matches!(
+        (left, right),
+        (Value::Number(_), Value::Number(_)) | (Value::Bool(_), Value::Bool(_))
+    )
+}
+
+fn tokenise<'a>(source: &'a str, operators: &OperatorTable) -> Result<Vec<Token<'a>>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut characters = source.char_indices().peekable();
+
+    while let Some(&(position, character)) = characters.peek() {
+        let kind = if character.is_whitespace() {
+            characters.next();
+            continue;
+        } else if character.is_ascii_digit() || character == '.' {
+            let mut end = position;
+            while let Some(&(index, digit)) = characters.peek() {
+                if !(digit.is_ascii_digit() || digit == '.') {
+                    break;
+                }
+                end = index + digit.len_utf8();
+                characters.next();
+            }
+            let number = source[position..end]
+                .parse()
+                .map_err(|_| ExprError::new(ExprErrorKind::InvalidNumber, position))?;
+            TokenKind::Number(number)
+        } else if character.is_alphabetic() || character == '_' {
+            let mut end = position;
+            while let Some(&(index, letter)) = characters.peek() {
+                if !(letter.is_alphanumeric() || letter == '_') {
+                    break;
+                }
+                end = index + letter.len_utf8();
+                characters.next();
+            }
+            match &source[position..end] {
+                "true" => TokenKind::Bool(true),
+                "false" => TokenKind::Bool(false),
+                name => TokenKind::Identifier(name.to_string()),
+            }
+        } else {
+            characters.next();
+            match character {
+                '(' => TokenKind::LeftParenthesis,
+                ')' => TokenKind::RightParenthesis,
+                ',' => TokenKind::Comma,
+                _ => {
+                    let length =
+                        operators
+                            .longest_symbol(&source[position..])
+                            .ok_or(ExprError::new(
+                                ExprErrorKind::UnexpectedCharacter(character),
+                                position,
+                            ))?;
+                    while characters
+                        .peek()
+                        .is_some_and(|&(index, _)| index < position + length)
+                    {
+                        characters.next();
+                    }
+                    TokenKind::Operator(&source[position..position + length])
+                }
+            }
+        };
+        tokens.push(Token { kind, position });
+    }
+
+    Ok(tokens)
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        Self::parse_with(source, &OperatorTable::new())
+    }
+
+    /// Parses `source` with the symbols, precedences and associativities of
+    /// `operators` in place of the standard ones.
+    pub fn parse_with(source: &str, operators: &OperatorTable) -> Result<Self, ExprError> {
+        let tokens = tokenise(source, operators)?;
+        let mut rpn = Vec::new();
+        let mut operator_stack = VecStack::new();
+        let mut argument_counts: VecStack<Option<usize>> = VecStack::new();
+        let mut expect_operand = true;
+
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(Token { kind, position }) = tokens.next() {
+            let starts_operand = match kind {
+                TokenKind::RightParenthesis | TokenKind::Comma => false,
+                TokenKind::Operator(_) => expect_operand,
+                _ => true,
+            };
+            if starts_operand {
+                if !expect_operand {
+                    return Err(ExprError::new(ExprErrorKind::ExpectedOperator, position));
+                }
+                if let Some(Some(count)) = argument_counts.peek_mut() {
+                    if *count == 0 {
+                        *count = 1;
+                    }
+                }
+            }
+
+            match kind {
+                TokenKind::Number(number) => {
+                    rpn.push((RpnToken::Value(Value::Number(number)), position));
+                    expect_operand = false;
+                }
+                TokenKind::Bool(boolean) => {
+                    rpn.push((RpnToken::Value(Value::Bool(boolean)), position));
+                    expect_operand = false;
+                }
+                TokenKind::Identifier(name) => {
+                    if tokens
+                        .peek()
+                        .is_some_and(|token| token.kind == TokenKind::LeftParenthesis)
+                    {
+                        let parenthesis = tokens.next().unwrap();
+                        operator_stack.push_unbounded(OperatorStackEntry::Function(name, position));
+                        operator_stack.push_unbounded(OperatorStackEntry::LeftParenthesis(
+                            parenthesis.position,
+                        ));
+                        argument_counts.push_unbounded(Some(0));
+                    } else {
+                        rpn.push((RpnToken::Variable(name), position));
+                        expect_operand = false;
+                    }
+                }
+                TokenKind::Operator(symbol) if expect_operand => {
+                    let (operator, precedence) = operators
+                        .find_unary(symbol)
+                        .ok_or(ExprError::new(ExprErrorKind::ExpectedOperand, position))?;
+                    operator_stack.push_unbounded(OperatorStackEntry::Operator(
+                        operator, precedence, position,
+                    ));
+                }
+                TokenKind::Operator(symbol) => {
+                    let (operator, precedence, associativity) = operators
+                        .find_binary(symbol)
+                        .ok_or(ExprError::new(ExprErrorKind::ExpectedOperator, position))?;
+                    while let Some(&OperatorStackEntry::Operator(_, top_precedence, _)) =
+                        operator_stack.peek()
+                    {
+                        if top_precedence > precedence
+                            || (top_precedence == precedence
+                                && associativity == Associativity::Left)
+                        {
+                            let Some(OperatorStackEntry::Operator(top, _, top_position)) =
+                                operator_stack.pop()
+                            else {
+                                unreachable!()
+                            };
+                            rpn.push((RpnToken::Operator(top), top_position));
+                        } else {
+                            break;
+                        }
+                    }
+                    operator_stack.push_unbounded(OperatorStackEntry::Operator(
+                        operator, precedence, position,
+                    ));
+                    expect_operand = true;
+                }
+                TokenKind::LeftParenthesis => {
+                    operator_stack.push_unbounded(OperatorStackEntry::LeftParenthesis(position));
+                    argument_counts.push_unbounded(None);
+                }
+                TokenKind::Comma => {
+                    if expect_operand {
+                        return Err(ExprError::new(ExprErrorKind::ExpectedOperand, position));
+                    }
+                    Self::pop_to_parenthesis(&mut operator_stack, &mut rpn);
+                    match argument_counts.peek_mut() {
+                        Some(Some(count)) => *count += 1,
+                        _ => return Err(ExprError::new(ExprErrorKind::UnexpectedComma, position)),
+                    }
+                    expect_operand = true;
+                }
+                TokenKind::RightParenthesis => {
+                    let argument_count = argument_counts.pop().ok_or(ExprError::new(
+                        ExprErrorKind::UnmatchedParenthesis,
+                        position,
+                    ))?;
+                    if expect_operand && argument_count != Some(0) {
+                        return Err(ExprError::new(ExprErrorKind::ExpectedOperand, position));
+                    }
+                    Self::pop_to_parenthesis(&mut operator_stack, &mut rpn);
+                    operator_stack.pop();
+                    if let Some(argument_count) = argument_count {
+                        let Some(OperatorStackEntry::Function(name, function_position)) =
+                            operator_stack.pop()
+                        else {
+                            unreachable!()
+                        };
+                        rpn.push((RpnToken::Function(name, argument_count), function_position));
+                    }
+                    expect_operand = false;
+                }
+            }
+        }
+
+        if expect_operand {
+            return Err(ExprError::new(ExprErrorKind::ExpectedOperand, source.len()));
+        }
+        while let Some(entry) = operator_stack.pop() {
+            match entry {
+                OperatorStackEntry::Operator(operator, _, position) => {
+                    rpn.push((RpnToken::Operator(operator), position))
+                }
+                OperatorStackEntry::LeftParenthesis(position)
+                | OperatorStackEntry::Function(_, position) => {
+                    return Err(ExprError::new(
+                        ExprErrorKind::UnmatchedParenthesis,
+                        position,
+                    ))
+                }
+            }
+        }
+
+        Ok(Expression { rpn })
+    }
+
+    fn pop_to_parenthesis(
+        operator_stack: &mut VecStack<OperatorStackEntry>,
+        rpn: &mut Vec<(RpnToken, usize)>,
+    ) {
+        while let Some(OperatorStackEntry::Operator(operator, _, position)) = operator_stack.peek()
+        {
+            rpn.push((RpnToken::Operator(*operator), *position));
+            operator_stack.pop();
+        }
+    }
+
+    pub fn evaluate(&self, context: &Context) -> Result<Value, ExprError> {
+        let mut stack = VecStack::new();
+
+        for (token, position) in &self.rpn {
+            let error = |kind| ExprError::new(kind, *position);
+            let value = match token {
+                RpnToken::Value(value) => *value,
+                RpnToken::Variable(name) => *context
+                    .variables
+                    .get(name)
+                    .ok_or_else(|| error(ExprErrorKind::UnknownVariable(name.clone())))?,
+                RpnToken::Operator(operator) if operator.is_unary() => {
+                    let operand = stack.pop().unwrap();
+                    operator.apply_unary(operand).map_err(error)?
+                }
+                RpnToken::Operator(operator) => {
+                    let right = stack.pop().unwrap();
+                    let left = stack.pop().unwrap();
+                    operator.apply(left, right).map_err(error)?
+                }
+                RpnToken::Function(name, argument_count) => {
+                    let (arity, function) = context
+                        .functions
+                        .get(name)
+                        .ok_or_else(|| error(ExprErrorKind::UnknownFunction(name.clone())))?;
+                    if let Some(arity) = arity {
+                        if arity != argument_count {
+                            return Err(error(ExprErrorKind::WrongArgumentCount {
+                                expected: *arity,
+                                found: *argument_count,
+                            }));
+                        }
+                    }
+                    let mut arguments: Vec<Value> =
+                        (0..*argument_count).map(|_| stack.pop().unwrap()).collect();
+                    arguments.reverse();
+                    function(&arguments).map_err(error)?
+                }
+            };
+            stack.push_unbounded(value);
+        }
+
+        Ok(stack.pop().unwrap())
+    }
+
+    /// The parsed expression in reverse Polish notation, one token per entry.
+    pub fn to_rpn(&self) -> Vec<String> {
+        self.rpn
+            .iter()
+            .map(|(token, _)| match token {
+                RpnToken::Value(value) => value.to_string(),
+                RpnToken::Variable(name) => name.clone(),
+                RpnToken::Operator(operator) => operator.symbol().to_string(),
+                RpnToken::Function(name, argument_count) => {
+                    format!("{}/{}", name, argument_count)
+                }
+            })
+            .collect()
+    }
+}
+
+impl Context {
+    /// A context with the `min`, `max`, `abs` and `if` functions defined.
+    pub fn new() -> Self {
+        let mut context = Context {
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+        };
+        context.set_function("min", None, |arguments| fold_numbers(arguments, f64::min));
+        context.set_function("max", None, |arguments| fold_numbers(arguments, f64::max));
+        context.set_function("abs", Some(1), |arguments| match arguments[0] {
+            Value::Number(number) => Ok(Value::Number(number.abs())),
+            Value::Bool(_) => Err(ExprErrorKind::TypeMismatch),
+        });
+        context.set_function("if", Some(3), |arguments| match arguments[0] {
+            Value::Bool(condition) => Ok(if condition {
+                arguments[1]
+            } else {
+                arguments[2]
+            }),
+            Value::Number(_) => Err(ExprErrorKind::TypeMismatch),
+        });
+        context
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: Value) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    /// Registers `function` under `name`. An `arity` of `None` accepts any
+    /// number of arguments.
+    pub fn set_function<F>(&mut self, name: &str, arity: Option<usize>, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, ExprErrorKind> + 'static,
+    {
+        self.functions
+            .insert(name.to_string(), (arity, Box::new(function)));
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn fold_numbers(arguments: &[Value], fold: fn(f64, f64) -> f64) -> Result<Value, ExprErrorKind> {
+    let mut numbers = arguments.iter().map(|argument| match argument {
+        Value::Number(number) => Ok(*number),
+        Value::Bool(_) => Err(ExprErrorKind::TypeMismatch),
+    });
+    let first = numbers.next().ok_or(ExprErrorKind::WrongArgumentCount {
+        expected: 1,
+        found: 0,
+    })??;
+    numbers
+        .try_fold(first, |accumulator, number| Ok(fold(accumulator, number?)))
+        .map(Value::Number)
+}
+
+pub fn evaluate(source: &str, context: &Context) -> Result<Value, ExprError> {
+    Expression::parse(source)?.evaluate(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(source: &str) -> f64 {
+        match evaluate(source, &Context::new()).unwrap() {
+            Value::Number(number) => number,
+            Value::Bool(_) => panic!("{} evaluated to a bool", source),
+        }
+    }
+
+    fn error(source: &str) -> ExprError {
+        evaluate(source, &Context::new()).unwrap_err()
+    }
+
+    #[test]
+    fn expr_precedence_and_associativity() {
+        assert!(number("1 + 2 * 3") == 7.0);
+        assert!(number("(1 + 2) * 3") == 9.0);
+        assert!(number("10 - 4 - 3") == 3.0);
+        assert!(number("2 ^ 3 ^ 2") == 512.0);
+        assert!(number("-2 ^ 2") == -4.0);
+        assert!(number("2 * -3") == -6.0);
+        assert!(number("7 % 4 + 0.5") == 3.5);
+        assert!(number("--1") == 1.0);
+    }
+
+    #[test]
+    fn expr_to_rpn() {
+        let test_expression = Expression::parse("a + b * (c - 1) ^ 2 ^ 3").unwrap();
+        let test_call = Expression::parse("max(1, -x, f())").unwrap();
+
+        assert!(
+            test_expression.to_rpn() == ["a", "b", "c", "1", "-", "2", "3", "^", "^", "*", "+"]
+        );
+        assert!(test_call.to_rpn() == ["1", "x", "neg", "f/0", "max/3"]);
+    }
+
+    #[test]
+    fn expr_custom_operator_table() {
+        let test_context = Context::new();
+        let mut test_operators = OperatorTable::new();
+        test_operators.set_binary("**", Operator::Power, 8, Associativity::Right);
+        test_operators.set_binary("<>", Operator::NotEqual, 3, Associativity::Left);
+        test_operators.set_binary("^", Operator::Power, 8, Associativity::Left);
+        test_operators.set_binary("+", Operator::Add, 7, Associativity::Left);
+        test_operators.set_unary("~", Operator::Negate, 9);
+        let parse = |source| Expression::parse_with(source, &test_operators).unwrap();
+
+        assert!(parse("2 ** 3 ** 2").evaluate(&test_context) == Ok(Value::Number(512.0)));
+        assert!(parse("2 ^ 3 ^ 2").evaluate(&test_context) == Ok(Value::Number(64.0)));
+        assert!(parse("1 <> 2").evaluate(&test_context) == Ok(Value::Bool(true)));
+        assert!(parse("2 * 3 + 4").evaluate(&test_context) == Ok(Value::Number(14.0)));
+        assert!(parse("~2 ** 2").evaluate(&test_context) == Ok(Value::Number(4.0)));
+        assert!(parse("~2 ** 2").to_rpn() == ["2", "neg", "2", "^"]);
+        assert!(error("2 ** 3").kind == ExprErrorKind::ExpectedOperand);
+        assert!(error("~2").kind == ExprErrorKind::UnexpectedCharacter('~'));
+    }
+
+    #[test]
+    #[should_panic]
+    fn expr_operator_table_rejects_unary_as_binary() {
+        OperatorTable::new().set_binary("~", Operator::Not, 7, Associativity::Left);
+    }
+
+    #[test]
+    fn expr_boolean_logic() {
+        let test_context = Context::new();
+
+        assert!(evaluate("1 < 2 && 3 >= 3", &test_context) == Ok(Value::Bool(true)));
+        assert!(evaluate("!(1 == 1) || 2 != 2", &test_context) == Ok(Value::Bool(false)));
+        assert!(evaluate("true || false && false", &test_context) == Ok(Value::Bool(true)));
+        assert!(evaluate("1 + 1 == 2", &test_context) == Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn expr_variables_and_functions() {
+        let mut test_context = Context::new();
+        test_context.set_variable("width", Value::Number(3.0));
+        test_context.set_variable("height", Value::Number(4.0));
+        test_context.set_variable("enabled", Value::Bool(true));
+        test_context.set_function("hypot", Some(2), |arguments| {
+            match (arguments[0], arguments[1]) {
+                (Value::Number(a), Value::Number(b)) => Ok(Value::Number((a * a + b * b).sqrt())),
+                _ => Err(ExprErrorKind::TypeMismatch),
+            }
+        });
+        test_context.set_function("zero", Some(0), |_| Ok(Value::Number(0.0)));
+
+        let test_expression =
+            Expression::parse("if(enabled, hypot(width, height), zero())").unwrap();
+        assert!(test_expression.evaluate(&test_context) == Ok(Value::Number(5.0)));
+        test_context.set_variable("enabled", Value::Bool(false));
+        assert!(test_expression.evaluate(&test_context) == Ok(Value::Number(0.0)));
+
+        assert!(evaluate("max(1, 5, 3) + min(2, -(1))", &test_context) == Ok(Value::Number(4.0)));
+        assert!(evaluate("abs(width - 10)", &test_context) == Ok(Value::Number(7.0)));
+    }
+
+    #[test]
+    fn expr_syntax_errors_report_positions() {
+        assert!(error("1 + ").kind == ExprErrorKind::ExpectedOperand);
+        assert!(error("1 + ").position == 4);
+        assert!(error("1 2").kind == ExprErrorKind::ExpectedOperator);
+        assert!(error("1 2").position == 2);
+        assert!(error("(1 + 2").kind == ExprErrorKind::UnmatchedParenthesis);
+        assert!(error("(1 + 2").position == 0);
+        assert!(error("1 + 2)").kind == ExprErrorKind::UnmatchedParenthesis);
+        assert!(error("1 + 2)").position == 5);
+        assert!(error("1 # 2").kind == ExprErrorKind::UnexpectedCharacter('#'));
+        assert!(error("1 # 2").position == 2);
+        assert!(error("1.2.3").kind == ExprErrorKind::InvalidNumber);
+        assert!(error("1, 2").kind == ExprErrorKind::UnexpectedComma);
+        assert!(error("max(1,)").kind == ExprErrorKind::ExpectedOperand);
+        assert!(error("max(1,)").position == 6);
+        assert!(error("* 2").kind == ExprErrorKind::ExpectedOperand);
+    }
+
+    #[test]
+    fn expr_evaluation_errors_report_positions() {
+        assert!(error("1 + x").kind == ExprErrorKind::UnknownVariable("x".to_string()));
+        assert!(error("1 + x").position == 4);
+        assert!(error("2 * nope(1)").kind == ExprErrorKind::UnknownFunction("nope".to_string()));
+        assert!(error("2 * nope(1)").position == 4);
+        assert!(
+            error("abs(1, 2)").kind
+                == ExprErrorKind::WrongArgumentCount {
+                    expected: 1,
+                    found: 2
+                }
+        );
+        assert!(error("1 + true").kind == ExprErrorKind::TypeMismatch);
+        assert!(error("1 + true").position == 2);
+        assert!(error("4 / (2 - 2)").kind == ExprErrorKind::DivisionByZero);
+        assert!(error("4 / (2 - 2)").position == 2);
+        assert!(error("1 + x").to_string() == "unknown variable 'x' at position 4");
+    }
+}
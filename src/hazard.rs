@@ -0,0 +1,195 @@
+use alloc::boxed::Box;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+const RECLAIM_THRESHOLD: usize = 64;
+
+/// A set of hazard pointers and the nodes retired against them. Lock-free
+/// structures protect a node before dereferencing it and retire it once it
+/// has been unlinked; retired nodes are freed only when no hazard points at them.
+pub(crate) struct HazardDomain {
+    records: AtomicPtr<HazardRecord>,
+    retired: AtomicPtr<RetiredNode>,
+    retired_count: AtomicUsize,
+}
+
+struct HazardRecord {
+    pointer: AtomicPtr<u8>,
+    active: AtomicBool,
+    next_record: *mut HazardRecord,
+}
+
+struct RetiredNode {
+    pointer: *mut u8,
+    reclaim: unsafe fn(*mut u8),
+    next_node: *mut RetiredNode,
+}
+
+pub(crate) struct HazardPointer<'a> {
+    record: &'a HazardRecord,
+}
+
+unsafe fn reclaim_box<T>(pointer: *mut u8) {
+    drop(unsafe { Box::from_raw(pointer as *mut T) });
+}
+
+impl HazardDomain {
+    pub(crate) const fn new() -> Self {
+        HazardDomain {
+            records: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_count: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn acquire(&self) -> HazardPointer<'_> {
+        let mut current_record = self.records.load(Ordering::SeqCst);
+        while !current_record.is_null() {
+            // SAFETY: records are only freed when the domain is dropped.
+            let record = unsafe { &*current_record };
+            if record
+                .active
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                return HazardPointer { record };
+            }
+            current_record = record.next_record;
+        }
+
+        let new_record = Box::into_raw(Box::new(HazardRecord {
+            pointer: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next_record: ptr::null_mut(),
+        }));
+        loop {
+            let head_record = self.records.load(Ordering::SeqCst);
+            // SAFETY: the new record is not yet shared with any other thread.
+            unsafe { (*new_record).next_record = head_record };
+            if self
+                .records
+                .compare_exchange(head_record, new_record, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                // SAFETY: records are only freed when the domain is dropped.
+                return HazardPointer {
+                    record: unsafe { &*new_record },
+                };
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `pointer` must come from `Box::into_raw`, must already be unreachable
+    /// for threads that have not protected it, and must be retired only once.
+    pub(crate) unsafe fn retire<T>(&self, pointer: *mut T) {
+        // Counted before publishing so a concurrent reclaim can not free the
+        // node and decrement the count first.
+        let retired_count = self.retired_count.fetch_add(1, Ordering::SeqCst) + 1;
+        self.push_retired(Box::into_raw(Box::new(RetiredNode {
+            pointer: pointer as *mut u8,
+            reclaim: reclaim_box::<T>,
+            next_node: ptr::null_mut(),
+        })));
+        if retired_count >= RECLAIM_THRESHOLD {
+            self.reclaim();
+        }
+    }
+
+    fn push_retired(&self, retired_node: *mut RetiredNode) {
+        loop {
+            let head_node = self.retired.load(Ordering::SeqCst);
+            // SAFETY: the retired node is owned by this thread until published.
+            unsafe { (*retired_node).next_node = head_node };
+            if self
+                .retired
+                .compare_exchange(head_node, retired_node, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    fn is_protected(&self, pointer: *mut u8) -> bool {
+        let mut current_record = self.records.load(Ordering::SeqCst);
+        while !current_record.is_null() {
+            // SAFETY: records are only freed when the domain is dropped.
+            let record = unsafe { &*current_record };
+            if record.pointer.load(Ordering::SeqCst) == pointer {
+                return true;
+            }
+            current_record = record.next_record;
+        }
+        false
+    }
+
+    fn reclaim(&self) {
+        let mut current_node = self.retired.swap(ptr::null_mut(), Ordering::SeqCst);
+        while !current_node.is_null() {
+            // SAFETY: swapping the list out gave this thread sole ownership of it.
+            let next_node = unsafe { (*current_node).next_node };
+            let (pointer, reclaim) = unsafe { ((*current_node).pointer, (*current_node).reclaim) };
+            if self.is_protected(pointer) {
+                self.push_retired(current_node);
+            } else {
+                // SAFETY: the node was unlinked before being retired and no
+                // hazard points at it, so no thread can still reach it.
+                unsafe {
+                    reclaim(pointer);
+                    drop(Box::from_raw(current_node));
+                }
+                self.retired_count.fetch_sub(1, Ordering::SeqCst);
+            }
+            current_node = next_node;
+        }
+    }
+}
+
+impl Drop for HazardDomain {
+    fn drop(&mut self) {
+        let mut current_node = *self.retired.get_mut();
+        while !current_node.is_null() {
+            // SAFETY: with `&mut self` no hazard pointer can be alive.
+            let retired_node = unsafe { Box::from_raw(current_node) };
+            unsafe { (retired_node.reclaim)(retired_node.pointer) };
+            current_node = retired_node.next_node;
+        }
+        let mut current_record = *self.records.get_mut();
+        while !current_record.is_null() {
+            // SAFETY: with `&mut self` no hazard pointer can borrow a record.
+            let record = unsafe { Box::from_raw(current_record) };
+            current_record = record.next_record;
+        }
+    }
+}
+
+impl HazardPointer<'_> {
+    /// Loads `source` and publishes it as hazardous, retrying until the
+    /// published value is still current so it can not have been retired.
+    pub(crate) fn protect<T>(&self, source: &AtomicPtr<T>) -> *mut T {
+        let mut pointer = source.load(Ordering::SeqCst);
+        loop {
+            self.record
+                .pointer
+                .store(pointer as *mut u8, Ordering::SeqCst);
+            let current_pointer = source.load(Ordering::SeqCst);
+            if current_pointer == pointer {
+                return pointer;
+            }
+            pointer = current_pointer;
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        self.record.pointer.store(ptr::null_mut(), Ordering::SeqCst);
+    }
+}
+
+impl Drop for HazardPointer<'_> {
+    fn drop(&mut self) {
+        self.clear();
+        self.record.active.store(false, Ordering::SeqCst);
+    }
+}
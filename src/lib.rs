@@ -1,7 +1,23 @@
-mod binary_heap;
-mod linked_list;
-mod queue;
-mod stack;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod binary_heap;
+pub mod brackets;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod expr;
+mod hazard;
+pub mod linked_list;
+pub mod monotonic_queue;
+pub mod monotonic_stack;
+pub mod queue;
+pub mod stack;
+#[cfg(test)]
+mod test_data;
+pub mod undo;
+
+pub use error::Error;
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
@@ -1,28 +1,12 @@
-use std::cell::{Ref, RefCell};
-use std::rc::Rc;
+use alloc::rc::Rc;
+use core::cell::{Ref, RefCell};
 
-#[derive(Debug)]
-pub struct Error {
-    details: String,
-}
-
-impl Error {
-    pub fn new(msg: &str) -> Error {
-        Error {
-            details: msg.to_string(),
-        }
-    }
-}
-
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.details)
-    }
-}
+use crate::error::Error;
 
 pub struct LinkedList<T> {
     head_node: Option<Rc<RefCell<LinkedListNode<T>>>>,
     tail_node: Option<Rc<RefCell<LinkedListNode<T>>>>,
+    count: usize,
 }
 
 pub struct IntoIter<T>(LinkedList<T>);
@@ -48,9 +32,14 @@ impl<T> LinkedList<T> {
         LinkedList {
             head_node: None,
             tail_node: None,
+            count: 0,
         }
     }
 
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
     pub fn is_empty(&self) -> bool {
         self.head_node.is_none()
     }
@@ -68,6 +57,7 @@ impl<T> LinkedList<T> {
                 self.head_node = Some(new_head);
             }
         }
+        self.count += 1;
     }
 
     pub fn insert_last(&mut self, data: T) {
@@ -83,6 +73,7 @@ impl<T> LinkedList<T> {
                 self.head_node = Some(new_tail);
             }
         }
+        self.count += 1;
     }
 
     pub fn remove_first(&mut self) -> Result<T, Error> {
@@ -95,9 +86,10 @@ impl<T> LinkedList<T> {
                     }
                     None => self.tail_node = None,
                 }
+                self.count -= 1;
                 Ok(Rc::try_unwrap(old_head).ok().unwrap().into_inner().data)
             }
-            None => Err(Error::new("list is empty")),
+            None => Err(Error::Empty),
         }
     }
 
@@ -111,27 +103,82 @@ impl<T> LinkedList<T> {
                     }
                     None => self.head_node = None,
                 }
+                self.count -= 1;
                 Ok(Rc::try_unwrap(old_tail).ok().unwrap().into_inner().data)
             }
-            None => Err(Error::new("list is empty")),
+            None => Err(Error::Empty),
         }
     }
 
-    pub fn peek_first(&self) -> Result<Ref<T>, Error> {
+    pub fn peek_first(&self) -> Result<Ref<'_, T>, Error> {
         self.head_node
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.data))
-            .ok_or(Error::new("list is empty"))
+            .ok_or(Error::Empty)
     }
 
-    pub fn peek_last(&self) -> Result<Ref<T>, Error> {
+    pub fn peek_last(&self) -> Result<Ref<'_, T>, Error> {
         self.tail_node
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.data))
-            .ok_or(Error::new("list is empty"))
+            .ok_or(Error::Empty)
+    }
+
+    /// Walks from whichever end of the list is nearer to `index`.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let node = self.node_at(index)?;
+        // SAFETY: nodes are only borrowed mutably through `&mut self`, so no
+        // mutable borrow can exist while the returned reference is alive.
+        unsafe { node.try_borrow_unguarded() }
+            .ok()
+            .map(|node| &node.data)
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let node = self.node_at(index)?;
+        // SAFETY: `&mut self` rules out any other borrow of the node.
+        Some(unsafe { &mut (*node.as_ptr()).data })
+    }
+
+    fn node_at(&self, index: usize) -> Option<&Rc<RefCell<LinkedListNode<T>>>> {
+        if index >= self.count {
+            return None;
+        }
+        let from_head = index < self.count / 2;
+        let mut current_node = if from_head {
+            self.head_node.as_ref()
+        } else {
+            self.tail_node.as_ref()
+        }?;
+        let steps = if from_head {
+            index
+        } else {
+            self.count - 1 - index
+        };
+        for _ in 0..steps {
+            // SAFETY: as in `get`, no mutable borrow can exist behind `&self`.
+            let node = unsafe { current_node.try_borrow_unguarded() }.ok()?;
+            current_node = if from_head {
+                node.next_node.as_ref()
+            } else {
+                node.previous_node.as_ref()
+            }?;
+        }
+        Some(current_node)
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -155,6 +202,7 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -164,27 +212,27 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3, test_data_4, test_data_5) =
             (10, 42, 59492957, -2222, -9683491);
 
-        test_list.insert_first(test_data_1.clone());
+        test_list.insert_first(test_data_1);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert first data object"
         );
-        test_list.insert_first(test_data_2.clone());
+        test_list.insert_first(test_data_2);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert second data object"
         );
-        test_list.insert_first(test_data_3.clone());
+        test_list.insert_first(test_data_3);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert third data object"
         );
-        test_list.insert_first(test_data_4.clone());
+        test_list.insert_first(test_data_4);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert fourth data object"
         );
-        test_list.insert_first(test_data_5.clone());
+        test_list.insert_first(test_data_5);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert fifth data object"
@@ -222,27 +270,27 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3, test_data_4, test_data_5) =
             (10, 42, 59492957, -2222, -9683491);
 
-        test_list.insert_last(test_data_1.clone());
+        test_list.insert_last(test_data_1);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert first data object"
         );
-        test_list.insert_first(test_data_2.clone());
+        test_list.insert_first(test_data_2);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert second data object"
         );
-        test_list.insert_first(test_data_3.clone());
+        test_list.insert_first(test_data_3);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert third data object"
         );
-        test_list.insert_first(test_data_4.clone());
+        test_list.insert_first(test_data_4);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert fourth data object"
         );
-        test_list.insert_first(test_data_5.clone());
+        test_list.insert_first(test_data_5);
         assert!(
             test_list.peek_first().is_ok(),
             "failed to insert fifth data object"
@@ -273,4 +321,24 @@ mod tests {
             "item was removed from empty list"
         );
     }
+
+    #[test]
+    fn linked_list_count_and_get() {
+        let mut test_list = LinkedList::new();
+
+        assert!(test_list.get(0).is_none());
+        for data in 0..7 {
+            test_list.insert_last(data);
+        }
+        assert!(test_list.count() == 7);
+        assert!((0..7).all(|index| test_list.get(index) == Some(&index)));
+        assert!(test_list.get(7).is_none());
+
+        *test_list.get_mut(1).unwrap() = 10;
+        *test_list.get_mut(5).unwrap() = 50;
+        assert!(test_list.remove_first().unwrap() == 0);
+        assert!(test_list.remove_last().unwrap() == 6);
+        assert!(test_list.count() == 5);
+        assert!(test_list.into_iter().collect::<Vec<_>>() == vec![10, 2, 3, 4, 50]);
+    }
 }
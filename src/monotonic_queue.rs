@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::marker::PhantomData;
+
+use crate::queue::{Deque, RingQueue};
+
+/// A sliding window that reports its greatest element under `compare` in
+/// O(1). Elements that can never be the greatest again, because a later
+/// element compares at least as great, are discarded as soon as it arrives.
+pub struct MonotonicQueue<T, F, D = RingQueue<(usize, T)>> {
+    deque: D,
+    compare: F,
+    pushed: usize,
+    popped: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Ord> MonotonicQueue<T, fn(&T, &T) -> Ordering> {
+    pub fn new_max() -> Self {
+        MonotonicQueue::with_comparator(T::cmp)
+    }
+
+    pub fn new_min() -> Self {
+        MonotonicQueue::with_comparator(|a, b| b.cmp(a))
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> MonotonicQueue<T, F> {
+    pub fn with_comparator(compare: F) -> Self {
+        MonotonicQueue::from_deque(RingQueue::new(), compare)
+    }
+}
+
+impl<T, F, D> MonotonicQueue<T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+    D: Deque<(usize, T)>,
+{
+    /// Uses `deque`, which must be empty, to hold the candidate elements
+    /// along with their position in the window.
+    pub fn from_deque(deque: D, compare: F) -> Self {
+        assert!(
+            deque.is_empty(),
+            "MonotonicQueue needs an empty deque to start from"
+        );
+        MonotonicQueue {
+            deque,
+            compare,
+            pushed: 0,
+            popped: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds `data` as the newest element of the window.
+    pub fn push(&mut self, data: T) {
+        while let Some((_, back)) = self.deque.back() {
+            if (self.compare)(back, &data) == Ordering::Greater {
+                break;
+            }
+            self.deque.pop_back();
+        }
+        self.deque.push_back((self.pushed, data));
+        self.pushed += 1;
+    }
+
+    /// Removes the oldest element of the window, if there is one.
+    pub fn pop(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+        if self
+            .deque
+            .front()
+            .is_some_and(|(position, _)| *position == self.popped)
+        {
+            self.deque.pop_front();
+        }
+        self.popped += 1;
+    }
+
+    /// The greatest element in the window, the newest one on a tie.
+    pub fn extremum(&self) -> Option<&T> {
+        self.deque.front().map(|(_, data)| data)
+    }
+
+    /// The number of elements in the window, including discarded ones.
+    pub fn count(&self) -> usize {
+        self.pushed - self.popped
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pushed == self.popped
+    }
+}
+
+/// The greatest element of every window of `window` consecutive elements,
+/// in O(n) overall.
+pub fn sliding_window_max<T: Ord + Clone>(data: &[T], window: usize) -> Vec<T> {
+    sliding_window(data, window, MonotonicQueue::new_max())
+}
+
+pub fn sliding_window_min<T: Ord + Clone>(data: &[T], window: usize) -> Vec<T> {
+    sliding_window(data, window, MonotonicQueue::new_min())
+}
+
+fn sliding_window<T, F>(data: &[T], window: usize, mut queue: MonotonicQueue<T, F>) -> Vec<T>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    assert!(window > 0, "sliding window needs a width of at least 1");
+    let mut extremes = Vec::with_capacity((data.len() + 1).saturating_sub(window));
+    for (index, value) in data.iter().enumerate() {
+        queue.push(value.clone());
+        if index + 1 > window {
+            queue.pop();
+        }
+        if index + 1 >= window {
+            extremes.extend(queue.extremum().cloned());
+        }
+    }
+    extremes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::ListDeque;
+    use crate::test_data::pseudo_random_data;
+
+    #[test]
+    fn monotonic_queue_window() {
+        let mut test_queue = MonotonicQueue::new_max();
+
+        assert!(test_queue.extremum().is_none());
+        test_queue.push(3);
+        test_queue.push(1);
+        test_queue.push(2);
+        assert!(*test_queue.extremum().unwrap() == 3);
+        assert!(test_queue.count() == 3);
+        test_queue.pop();
+        assert!(*test_queue.extremum().unwrap() == 2);
+        test_queue.pop();
+        test_queue.pop();
+        test_queue.pop();
+        assert!(test_queue.extremum().is_none());
+        assert!(test_queue.is_empty());
+        test_queue.push(5);
+        assert!(*test_queue.extremum().unwrap() == 5);
+    }
+
+    #[test]
+    fn monotonic_queue_sliding_windows() {
+        let test_data = [1, 3, -1, -3, 5, 3, 6, 7];
+
+        assert!(sliding_window_max(&test_data, 3) == [3, 3, 5, 5, 6, 7]);
+        assert!(sliding_window_min(&test_data, 3) == [-1, -3, -3, -3, 3, 3]);
+        assert!(sliding_window_max(&test_data, 1) == test_data);
+        assert!(sliding_window_min(&test_data, 8) == [-3]);
+        assert!(sliding_window_max(&test_data, 9).is_empty());
+    }
+
+    #[test]
+    fn monotonic_queue_matches_brute_force() {
+        let test_data = pseudo_random_data(1000, 50);
+
+        for window in [1, 2, 7, 64] {
+            let brute_max: Vec<u64> = test_data
+                .windows(window)
+                .map(|values| *values.iter().max().unwrap())
+                .collect();
+            let brute_min: Vec<u64> = test_data
+                .windows(window)
+                .map(|values| *values.iter().min().unwrap())
+                .collect();
+            assert!(sliding_window_max(&test_data, window) == brute_max);
+            assert!(sliding_window_min(&test_data, window) == brute_min);
+        }
+    }
+
+    #[test]
+    fn monotonic_queue_custom_comparator_and_deque() {
+        let test_words = ["ccc", "a", "bb", "dddd", "e", "ff"];
+        let mut test_queue = MonotonicQueue::from_deque(ListDeque::new(), |a: &&str, b: &&str| {
+            a.len().cmp(&b.len())
+        });
+        let mut longest = Vec::new();
+
+        for (index, word) in test_words.iter().enumerate() {
+            test_queue.push(*word);
+            if index >= 2 {
+                test_queue.pop();
+            }
+            longest.push(*test_queue.extremum().unwrap());
+        }
+        assert!(longest == ["ccc", "ccc", "bb", "dddd", "dddd", "ff"]);
+    }
+
+    #[test]
+    fn monotonic_queue_keeps_newest_on_tie() {
+        let mut test_queue =
+            MonotonicQueue::with_comparator(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+
+        test_queue.push("ab");
+        test_queue.push("cd");
+        assert!(*test_queue.extremum().unwrap() == "cd");
+        test_queue.push("e");
+        test_queue.pop();
+        assert!(*test_queue.extremum().unwrap() == "cd");
+        test_queue.pop();
+        assert!(*test_queue.extremum().unwrap() == "e");
+    }
+}
@@ -0,0 +1,389 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::AddAssign;
+
+use crate::stack::{Stack, UnboundedStack, VecStack};
+
+/// For each index, the index of the nearest later element that compares
+/// greater under `compare`, if any.
+pub fn next_greater_by<T, F>(data: &[T], mut compare: F) -> Vec<Option<usize>>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut next = vec![None; data.len()];
+    let mut pending = VecStack::new();
+
+    for (index, value) in data.iter().enumerate() {
+        while let Some(&top) = pending.peek() {
+            if compare(&data[top], value) != Ordering::Less {
+                break;
+            }
+            next[top] = Some(index);
+            pending.pop();
+        }
+        pending.push_unbounded(index);
+    }
+    next
+}
+
+/// For each index, the index of the nearest earlier element that compares
+/// greater under `compare`, if any.
+pub fn previous_greater_by<T, F>(data: &[T], mut compare: F) -> Vec<Option<usize>>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut previous = Vec::with_capacity(data.len());
+    let mut candidates: VecStack<usize> = VecStack::new();
+
+    for value in data {
+        while let Some(&top) = candidates.peek() {
+            if compare(&data[top], value) == Ordering::Greater {
+                break;
+            }
+            candidates.pop();
+        }
+        previous.push(candidates.peek().copied());
+        candidates.push_unbounded(previous.len() - 1);
+    }
+    previous
+}
+
+pub fn next_greater<T: Ord>(data: &[T]) -> Vec<Option<usize>> {
+    next_greater_by(data, T::cmp)
+}
+
+pub fn next_smaller<T: Ord>(data: &[T]) -> Vec<Option<usize>> {
+    next_greater_by(data, |a, b| b.cmp(a))
+}
+
+pub fn next_smaller_by<T, F>(data: &[T], mut compare: F) -> Vec<Option<usize>>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    next_greater_by(data, |a, b| compare(b, a))
+}
+
+pub fn previous_greater<T: Ord>(data: &[T]) -> Vec<Option<usize>> {
+    previous_greater_by(data, T::cmp)
+}
+
+pub fn previous_smaller<T: Ord>(data: &[T]) -> Vec<Option<usize>> {
+    previous_greater_by(data, |a, b| b.cmp(a))
+}
+
+pub fn previous_smaller_by<T, F>(data: &[T], mut compare: F) -> Vec<Option<usize>>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    previous_greater_by(data, |a, b| compare(b, a))
+}
+
+/// For each day, how many consecutive days ending on it had a price no
+/// greater than that day's price.
+pub fn stock_span_by<T, F>(prices: &[T], compare: F) -> Vec<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    previous_greater_by(prices, compare)
+        .into_iter()
+        .enumerate()
+        .map(|(index, previous)| previous.map_or(index + 1, |previous| index - previous))
+        .collect()
+}
+
+pub fn stock_span<T: Ord>(prices: &[T]) -> Vec<usize> {
+    stock_span_by(prices, T::cmp)
+}
+
+/// The greatest `area(height, width)` over the rectangles that fit under the
+/// histogram, with the heights ordered by `compare`.
+pub fn largest_rectangle_by<T, A, F, G>(heights: &[T], mut compare: F, mut area: G) -> A
+where
+    A: Ord + Default,
+    F: FnMut(&T, &T) -> Ordering,
+    G: FnMut(&T, usize) -> A,
+{
+    let mut largest_area = A::default();
+    let mut rising: VecStack<usize> = VecStack::new();
+
+    for index in 0..=heights.len() {
+        // Past the last bar every remaining bar is popped, as if by a bar of
+        // height zero.
+        while let Some(&top) = rising.peek() {
+            if heights
+                .get(index)
+                .is_some_and(|height| compare(&heights[top], height) == Ordering::Less)
+            {
+                break;
+            }
+            rising.pop();
+            let left = rising.peek().map_or(0, |&left| left + 1);
+            largest_area = largest_area.max(area(&heights[top], index - left));
+        }
+        rising.push_unbounded(index);
+    }
+    largest_area
+}
+
+/// The area of the largest rectangle that fits under the histogram. The area
+/// is counted in `usize` so narrow heights can not overflow; panics if it does
+/// not fit even there.
+pub fn largest_rectangle<T: Copy + Ord + Into<usize>>(heights: &[T]) -> usize {
+    largest_rectangle_by(heights, T::cmp, |&height, width| area(height.into(), width))
+}
+
+/// The area of the largest rectangle of cells that are `filled` in a
+/// row-major matrix. Panics if the rows differ in length.
+pub fn maximal_rectangle_by<T, R, F>(matrix: &[R], mut filled: F) -> usize
+where
+    R: AsRef<[T]>,
+    F: FnMut(&T) -> bool,
+{
+    let width = matrix.first().map_or(0, |row| row.as_ref().len());
+    let mut heights = vec![0usize; width];
+    let mut largest_area = 0;
+
+    for row in matrix {
+        let row = row.as_ref();
+        assert!(
+            row.len() == width,
+            "maximal_rectangle needs every row to have the same length"
+        );
+        for (height, cell) in heights.iter_mut().zip(row) {
+            *height = if filled(cell) { *height + 1 } else { 0 };
+        }
+        largest_area = largest_area.max(largest_rectangle(&heights));
+    }
+    largest_area
+}
+
+/// The area of the largest all-`true` rectangle in a row-major matrix.
+pub fn maximal_rectangle<R: AsRef<[bool]>>(matrix: &[R]) -> usize {
+    maximal_rectangle_by(matrix, |&cell| cell)
+}
+
+/// The water held between the bars of an elevation map, with the heights
+/// ordered by `compare`. `volume(level, floor, width)` gives the water in a
+/// pool of `width` bars that fills from `floor` up to `level`.
+pub fn trap_rain_water_by<T, A, F, G>(heights: &[T], mut compare: F, mut volume: G) -> A
+where
+    A: AddAssign + Default,
+    F: FnMut(&T, &T) -> Ordering,
+    G: FnMut(&T, &T, usize) -> A,
+{
+    let mut water = A::default();
+    let mut walls: VecStack<usize> = VecStack::new();
+
+    for (index, height) in heights.iter().enumerate() {
+        while let Some(&floor) = walls.peek() {
+            if compare(&heights[floor], height) != Ordering::Less {
+                break;
+            }
+            walls.pop();
+            if let Some(&left) = walls.peek() {
+                let level = match compare(&heights[left], height) {
+                    Ordering::Less => &heights[left],
+                    _ => height,
+                };
+                water += volume(level, &heights[floor], index - left - 1);
+            }
+        }
+        walls.push_unbounded(index);
+    }
+    water
+}
+
+/// The units of water held between the bars of an elevation map, counted in
+/// `usize` so narrow heights can not overflow; panics if the total does not
+/// fit even there.
+pub fn trap_rain_water<T: Copy + Ord + Into<usize>>(heights: &[T]) -> usize {
+    let CheckedSum(water) = trap_rain_water_by(heights, T::cmp, |&level, &floor, width| {
+        CheckedSum(area(level.into() - floor.into(), width))
+    });
+    water
+}
+
+#[derive(Default)]
+struct CheckedSum(usize);
+
+impl AddAssign for CheckedSum {
+    fn add_assign(&mut self, other: CheckedSum) {
+        self.0 = self
+            .0
+            .checked_add(other.0)
+            .expect("trapped water does not fit in usize");
+    }
+}
+
+fn area(height: usize, width: usize) -> usize {
+    height
+        .checked_mul(width)
+        .expect("rectangle area does not fit in usize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::pseudo_random_data;
+
+    #[test]
+    fn monotonic_next_and_previous() {
+        let test_data = [2, 7, 3, 5, 4, 6, 8];
+
+        assert!(
+            next_greater(&test_data)
+                == [Some(1), Some(6), Some(3), Some(5), Some(5), Some(6), None]
+        );
+        assert!(next_smaller(&test_data) == [None, Some(2), None, Some(4), None, None, None]);
+        assert!(
+            previous_greater(&test_data) == [None, None, Some(1), Some(1), Some(3), Some(1), None]
+        );
+        assert!(
+            previous_smaller(&test_data)
+                == [None, Some(0), Some(0), Some(2), Some(2), Some(4), Some(5)]
+        );
+    }
+
+    #[test]
+    fn monotonic_matches_brute_force() {
+        let test_data = pseudo_random_data(300, 20);
+        let brute_next = |greater: bool| -> Vec<Option<usize>> {
+            (0..test_data.len())
+                .map(|i| {
+                    (i + 1..test_data.len()).find(|&j| {
+                        if greater {
+                            test_data[j] > test_data[i]
+                        } else {
+                            test_data[j] < test_data[i]
+                        }
+                    })
+                })
+                .collect()
+        };
+        let brute_previous = |greater: bool| -> Vec<Option<usize>> {
+            (0..test_data.len())
+                .map(|i| {
+                    (0..i).rev().find(|&j| {
+                        if greater {
+                            test_data[j] > test_data[i]
+                        } else {
+                            test_data[j] < test_data[i]
+                        }
+                    })
+                })
+                .collect()
+        };
+
+        assert!(next_greater(&test_data) == brute_next(true));
+        assert!(next_smaller(&test_data) == brute_next(false));
+        assert!(previous_greater(&test_data) == brute_previous(true));
+        assert!(previous_smaller(&test_data) == brute_previous(false));
+    }
+
+    #[test]
+    fn monotonic_custom_comparator() {
+        let test_words = ["a", "ccc", "bb", "dddd", "e"];
+
+        assert!(
+            next_greater_by(&test_words, |a, b| a.len().cmp(&b.len()))
+                == [Some(1), Some(3), Some(3), None, None]
+        );
+        assert!(
+            next_smaller_by(&test_words, |a, b| a.len().cmp(&b.len()))
+                == [None, Some(2), Some(4), Some(4), None]
+        );
+        assert!(
+            previous_smaller_by(&test_words, |a, b| a.len().cmp(&b.len()))
+                == [None, Some(0), Some(0), Some(2), None]
+        );
+        assert!(stock_span_by(&test_words, |a, b| a.len().cmp(&b.len())) == [1, 2, 1, 4, 1]);
+    }
+
+    #[test]
+    fn monotonic_stock_span() {
+        assert!(stock_span(&[100, 80, 60, 70, 60, 75, 85]) == [1, 1, 1, 2, 1, 4, 6]);
+        assert!(stock_span::<i32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn monotonic_largest_rectangle() {
+        assert!(largest_rectangle(&[2usize, 1, 5, 6, 2, 3]) == 10);
+        assert!(largest_rectangle(&[2usize, 4]) == 4);
+        assert!(largest_rectangle(&[3usize, 3, 3]) == 9);
+        assert!(largest_rectangle::<usize>(&[]) == 0);
+
+        let test_heights: Vec<usize> = pseudo_random_data(200, 15)
+            .into_iter()
+            .map(|height| height as usize)
+            .collect();
+        let brute_force = (0..test_heights.len())
+            .flat_map(|i| (i..test_heights.len()).map(move |j| (i, j)))
+            .map(|(i, j)| test_heights[i..=j].iter().min().unwrap() * (j - i + 1))
+            .max()
+            .unwrap();
+        assert!(largest_rectangle(&test_heights) == brute_force);
+    }
+
+    #[test]
+    fn monotonic_maximal_rectangle() {
+        let test_matrix = [
+            [true, false, true, false, false],
+            [true, false, true, true, true],
+            [true, true, true, true, true],
+            [true, false, false, true, false],
+        ];
+        let test_empty: [[bool; 0]; 0] = [];
+
+        assert!(maximal_rectangle(&test_matrix) == 6);
+        assert!(maximal_rectangle(&test_empty) == 0);
+        assert!(maximal_rectangle(&[vec![false, false]]) == 0);
+    }
+
+    #[test]
+    fn monotonic_rectangles_and_water_by_comparator() {
+        let test_bars = ["ab", "a", "abcde", "abcdef", "ab", "abc"];
+        let test_matrix = ["10100", "10111", "11111", "10010"].map(str::as_bytes);
+        let by_length = |a: &&str, b: &&str| a.len().cmp(&b.len());
+
+        assert!(largest_rectangle_by(&test_bars, by_length, |bar, width| bar.len() * width) == 10);
+        assert!(
+            trap_rain_water_by(&test_bars, by_length, |level, floor, width| {
+                (level.len() - floor.len()) * width
+            }) == 2
+        );
+        assert!(maximal_rectangle_by(&test_matrix, |&cell| cell == b'1') == 6);
+        assert!(largest_rectangle(&[2u8, 1, 5, 6, 2, 3]) == 10);
+        assert!(
+            trap_rain_water_by(&[4i64, 2, 0, 3, 2, 5], i64::cmp, |level, floor, width| {
+                (level - floor) * width as i64
+            }) == 9
+        );
+    }
+
+    #[test]
+    fn monotonic_narrow_heights_do_not_overflow() {
+        let test_tall = [200u8; 300];
+        let test_pool: Vec<u8> = [255].into_iter().chain([0; 300]).chain([255]).collect();
+
+        assert!(largest_rectangle(&[200u8, 200]) == 400);
+        assert!(largest_rectangle(&test_tall) == 60000);
+        assert!(largest_rectangle(&[u16::MAX, u16::MAX, 1]) == 131070);
+        assert!(trap_rain_water(&test_pool) == 76500);
+        assert!(trap_rain_water(&[u16::MAX, 0, 0, u16::MAX]) == 131070);
+    }
+
+    #[test]
+    #[should_panic]
+    fn monotonic_maximal_rectangle_jagged_rows() {
+        maximal_rectangle(&[vec![true, true], vec![true], vec![true, true]]);
+    }
+
+    #[test]
+    fn monotonic_trap_rain_water() {
+        assert!(trap_rain_water(&[0usize, 1, 0, 2, 1, 0, 1, 3, 2, 1, 2, 1]) == 6);
+        assert!(trap_rain_water(&[4usize, 2, 0, 3, 2, 5]) == 9);
+        assert!(trap_rain_water(&[1usize, 2, 3]) == 0);
+        assert!(trap_rain_water::<usize>(&[]) == 0);
+    }
+}
@@ -1,39 +1,111 @@
-trait Queue<T> {
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::iter::Chain;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Index, IndexMut};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::binary_heap::{BinaryHeap, BinaryMaxHeap, BinaryMinHeap};
+use crate::error::Error;
+use crate::hazard::HazardDomain;
+use crate::linked_list::LinkedList;
+use crate::stack::{PersistentStack, Stack, UnboundedStack, VecStack};
+
+pub trait Queue<T> {
     fn queue(&mut self, data: T);
 
-    fn dequeue(&mut self) -> Result<T, Error>;
+    fn dequeue(&mut self) -> Option<T>;
 
-    fn peek(&self) -> Result<T, Error>;
+    fn peek(&self) -> Option<&T>;
+
+    fn peek_mut(&mut self) -> Option<&mut T>;
 
     fn count(&self) -> usize;
 
     fn is_empty(&self) -> bool;
-}
 
-pub struct ShuffleQueue<T> {
-    queue: Vec<T>,
-    count: usize,
+    fn try_dequeue(&mut self) -> Result<T, Error> {
+        self.dequeue().ok_or(Error::Empty)
+    }
+
+    fn try_peek(&self) -> Result<&T, Error> {
+        self.peek().ok_or(Error::Empty)
+    }
 }
 
-#[derive(Debug)]
-pub struct Error {
-    details: String,
+/// The `Queue` operations for queues shared between threads, which take
+/// `&self` and can not hand out references to their elements.
+pub trait SharedQueue<T> {
+    fn queue(&self, data: T);
+
+    fn dequeue(&self) -> Option<T>;
+
+    fn count(&self) -> usize;
+
+    fn is_empty(&self) -> bool;
+
+    fn try_dequeue(&self) -> Result<T, Error> {
+        self.dequeue().ok_or(Error::Empty)
+    }
 }
 
-impl Error {
-    pub fn new(msg: &str) -> Error {
-        Error {
-            details: msg.to_string(),
+/// A queue that can also be pushed to and popped from at the back and front
+/// respectively. `Queue::queue` and `Queue::dequeue` act as `push_back` and
+/// `pop_front`.
+pub trait Deque<T>: Queue<T> {
+    fn push_front(&mut self, data: T);
+
+    fn push_back(&mut self, data: T);
+
+    fn pop_front(&mut self) -> Option<T>;
+
+    fn pop_back(&mut self) -> Option<T>;
+
+    fn front(&self) -> Option<&T>;
+
+    fn back(&self) -> Option<&T>;
+
+    /// The element `index` places from the front.
+    fn get(&self, index: usize) -> Option<&T>;
+
+    /// Moves the first `steps` elements to the back, wrapping `steps` around
+    /// the length of the deque.
+    fn rotate_left(&mut self, steps: usize) {
+        if self.is_empty() {
+            return;
+        }
+        for _ in 0..steps % self.count() {
+            if let Some(data) = self.pop_front() {
+                self.push_back(data);
+            }
         }
     }
-}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.details)
+    /// Moves the last `steps` elements to the front, wrapping `steps` around
+    /// the length of the deque.
+    fn rotate_right(&mut self, steps: usize) {
+        if self.is_empty() {
+            return;
+        }
+        for _ in 0..steps % self.count() {
+            if let Some(data) = self.pop_back() {
+                self.push_front(data);
+            }
+        }
     }
 }
 
+pub struct ShuffleQueue<T> {
+    queue: Vec<T>,
+    count: usize,
+}
+
 impl<T> ShuffleQueue<T> {
     pub fn new() -> Self {
         ShuffleQueue {
@@ -43,29 +115,223 @@ impl<T> ShuffleQueue<T> {
     }
 }
 
-impl<T: Clone> Queue<T> for ShuffleQueue<T> {
+impl<T> Default for ShuffleQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Queue<T> for ShuffleQueue<T> {
     fn queue(&mut self, data: T) {
         self.queue.insert(self.count, data);
         self.count += 1;
     }
 
-    fn dequeue(&mut self) -> Result<T, Error> {
+    fn dequeue(&mut self) -> Option<T> {
         if self.count == 0 {
-            Err(Error::new("can not dequeue from empty queue"))
+            None
         } else {
             self.count -= 1;
-            Ok(self.queue.remove(0))
+            Some(self.queue.remove(0))
+        }
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.queue.first()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.queue.first_mut()
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+pub struct RingQueue<T> {
+    buffer: Box<[MaybeUninit<T>]>,
+    head: usize,
+    count: usize,
+}
+
+pub struct RingQueueIter<'a, T>(Chain<core::slice::Iter<'a, T>, core::slice::Iter<'a, T>>);
+
+pub struct RingQueueIterMut<'a, T>(Chain<core::slice::IterMut<'a, T>, core::slice::IterMut<'a, T>>);
+
+pub struct RingQueueIntoIter<T>(RingQueue<T>);
+
+impl<T> RingQueue<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        RingQueue {
+            buffer: Self::allocate(capacity),
+            head: 0,
+            count: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        if self.capacity() > self.count {
+            self.resize(self.count);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.count {
+            // SAFETY: the first `count` logical slots are initialised.
+            Some(unsafe { self.buffer[self.physical_index(index)].assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.count {
+            let physical_index = self.physical_index(index);
+            // SAFETY: the first `count` logical slots are initialised.
+            Some(unsafe { self.buffer[physical_index].assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// The queued elements front to back, split where the buffer wraps.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (front, back) = self.slice_ranges();
+        // SAFETY: both ranges cover only initialised slots.
+        unsafe {
+            (
+                &*(&self.buffer[front] as *const [MaybeUninit<T>] as *const [T]),
+                &*(&self.buffer[back] as *const [MaybeUninit<T>] as *const [T]),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front, back) = self.slice_ranges();
+        let (wrapped, unwrapped) = self.buffer.split_at_mut(front.start);
+        // SAFETY: both ranges cover only initialised slots and do not overlap.
+        unsafe {
+            (
+                &mut *(&mut unwrapped[..front.len()] as *mut [MaybeUninit<T>] as *mut [T]),
+                &mut *(&mut wrapped[back] as *mut [MaybeUninit<T>] as *mut [T]),
+            )
+        }
+    }
+
+    pub fn iter(&self) -> RingQueueIter<'_, T> {
+        let (front, back) = self.as_slices();
+        RingQueueIter(front.iter().chain(back.iter()))
+    }
+
+    pub fn iter_mut(&mut self) -> RingQueueIterMut<'_, T> {
+        let (front, back) = self.as_mut_slices();
+        RingQueueIterMut(front.iter_mut().chain(back.iter_mut()))
+    }
+
+    pub fn clear(&mut self) {
+        while self.dequeue().is_some() {}
+    }
+
+    fn allocate(capacity: usize) -> Box<[MaybeUninit<T>]> {
+        (0..capacity).map(|_| MaybeUninit::uninit()).collect()
+    }
+
+    fn physical_index(&self, index: usize) -> usize {
+        let physical_index = self.head + index;
+        if physical_index >= self.capacity() {
+            physical_index - self.capacity()
+        } else {
+            physical_index
+        }
+    }
+
+    fn slice_ranges(&self) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+        if self.head + self.count <= self.capacity() {
+            (self.head..self.head + self.count, 0..0)
+        } else {
+            (
+                self.head..self.capacity(),
+                0..self.head + self.count - self.capacity(),
+            )
+        }
+    }
+
+    fn reserve_one(&mut self) {
+        if self.count == self.capacity() {
+            self.resize((self.capacity() * 2).max(4));
+        }
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        let mut buffer = Self::allocate(capacity);
+        for (index, slot) in buffer.iter_mut().take(self.count).enumerate() {
+            // SAFETY: each initialised slot is moved out exactly once and the
+            // old buffer is then freed without dropping its contents.
+            slot.write(unsafe { self.buffer[self.physical_index(index)].assume_init_read() });
+        }
+        self.buffer = buffer;
+        self.head = 0;
+    }
+}
+
+impl<T> Default for RingQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for RingQueue<T> {
+    fn drop(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        // SAFETY: the slices hold the initialised elements, dropped only here.
+        unsafe {
+            ptr::drop_in_place(front);
+            ptr::drop_in_place(back);
         }
     }
+}
+
+impl<T> Queue<T> for RingQueue<T> {
+    fn queue(&mut self, data: T) {
+        self.reserve_one();
+        let tail_index = self.physical_index(self.count);
+        self.buffer[tail_index].write(data);
+        self.count += 1;
+    }
 
-    fn peek(&self) -> Result<T, Error> {
+    fn dequeue(&mut self) -> Option<T> {
         if self.count == 0 {
-            Err(Error::new("can not peek empty queue"))
+            None
         } else {
-            Ok(self.queue[0].clone())
+            // SAFETY: the head slot is initialised and stops being counted.
+            let data = unsafe { self.buffer[self.head].assume_init_read() };
+            self.head = self.physical_index(1);
+            self.count -= 1;
+            Some(data)
         }
     }
 
+    fn peek(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
     fn count(&self) -> usize {
         self.count
     }
@@ -75,23 +341,3303 @@ impl<T: Clone> Queue<T> for ShuffleQueue<T> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<T> Deque<T> for RingQueue<T> {
+    fn push_front(&mut self, data: T) {
+        self.reserve_one();
+        self.head = self.physical_index(self.capacity() - 1);
+        self.buffer[self.head].write(data);
+        self.count += 1;
+    }
 
-    #[test]
-    fn shuffle_queue() {
-        let mut test_queue = ShuffleQueue::new();
-        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+    fn push_back(&mut self, data: T) {
+        self.queue(data);
+    }
 
-        test_queue.queue(test_data_1.clone());
-        test_queue.queue(test_data_2.clone());
-        test_queue.queue(test_data_3.clone());
+    fn pop_front(&mut self) -> Option<T> {
+        self.dequeue()
+    }
 
-        assert!(test_queue.dequeue().unwrap() == test_data_1);
-        assert!(test_queue.peek().unwrap() == test_data_2);
-        assert!(test_queue.dequeue().unwrap() == test_data_2);
-        assert!(test_queue.dequeue().unwrap() == test_data_3);
-        assert!(test_queue.dequeue().is_err());
+    fn pop_back(&mut self) -> Option<T> {
+        if self.count == 0 {
+            None
+        } else {
+            self.count -= 1;
+            // SAFETY: the tail slot is initialised and has stopped being counted.
+            Some(unsafe { self.buffer[self.physical_index(self.count)].assume_init_read() })
+        }
+    }
+
+    fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    fn back(&self) -> Option<&T> {
+        self.count.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        RingQueue::get(self, index)
+    }
+}
+
+impl<T> Index<usize> for RingQueue<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("RingQueue index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for RingQueue<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("RingQueue index out of bounds")
+    }
+}
+
+impl<T: Clone> Clone for RingQueue<T> {
+    fn clone(&self) -> Self {
+        let mut new_queue = RingQueue::with_capacity(self.count);
+        new_queue.extend(self.iter().cloned());
+        new_queue
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RingQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T> Iterator for RingQueueIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T> Iterator for RingQueueIterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> Iterator for RingQueueIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.dequeue()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.count, Some(self.0.count))
+    }
+}
+
+impl<T> IntoIterator for RingQueue<T> {
+    type Item = T;
+    type IntoIter = RingQueueIntoIter<T>;
+
+    fn into_iter(self) -> RingQueueIntoIter<T> {
+        RingQueueIntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RingQueue<T> {
+    type Item = &'a T;
+    type IntoIter = RingQueueIter<'a, T>;
+
+    fn into_iter(self) -> RingQueueIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut RingQueue<T> {
+    type Item = &'a mut T;
+    type IntoIter = RingQueueIterMut<'a, T>;
+
+    fn into_iter(self) -> RingQueueIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for RingQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = RingQueue::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<T> Extend<T> for RingQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.queue(data);
+        }
+    }
+}
+
+/// A fixed-capacity ring that overwrites its oldest element when full, so it
+/// always holds the most recent `capacity` elements. As a `Queue`, `queue`
+/// never fails; it silently drops the oldest element to make room.
+pub struct CircularBuffer<T> {
+    queue: RingQueue<T>,
+    capacity: usize,
+    overwrite_count: usize,
+}
+
+impl<T> CircularBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "CircularBuffer needs a capacity of at least 1"
+        );
+        CircularBuffer {
+            queue: RingQueue::with_capacity(capacity),
+            capacity,
+            overwrite_count: 0,
+        }
+    }
+
+    /// Adds `data` as the newest element, returning the oldest element if it
+    /// had to be overwritten.
+    pub fn push(&mut self, data: T) -> Option<T> {
+        let overwritten = if self.is_full() {
+            self.overwrite_count += 1;
+            self.queue.dequeue()
+        } else {
+            None
+        };
+        self.queue.queue(data);
+        overwritten
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.count() == self.capacity
+    }
+
+    /// How many elements have been overwritten since the buffer was created.
+    pub fn overwrite_count(&self) -> usize {
+        self.overwrite_count
+    }
+
+    /// The element `index` places from the oldest.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.queue.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.queue.get_mut(index)
+    }
+
+    /// The elements oldest to newest, split where the buffer wraps.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        self.queue.as_slices()
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        self.queue.as_mut_slices()
+    }
+
+    /// Iterates from the oldest element to the newest.
+    pub fn iter(&self) -> RingQueueIter<'_, T> {
+        self.queue.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> RingQueueIterMut<'_, T> {
+        self.queue.iter_mut()
+    }
+
+    /// Pushes clones of `data` in order. Elements that would be overwritten
+    /// by later ones in `data` are not cloned at all.
+    pub fn extend_from_slice(&mut self, data: &[T])
+    where
+        T: Clone,
+    {
+        let skipped = data.len().saturating_sub(self.capacity);
+        self.overwrite_count += skipped;
+        for data in &data[skipped..] {
+            self.push(data.clone());
+        }
+    }
+
+    /// Copies the elements out, oldest first.
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let (front, back) = self.as_slices();
+        let mut snapshot = Vec::with_capacity(self.queue.count());
+        snapshot.extend_from_slice(front);
+        snapshot.extend_from_slice(back);
+        snapshot
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+impl<T> Queue<T> for CircularBuffer<T> {
+    /// Overwrites the oldest element if the buffer is full.
+    fn queue(&mut self, data: T) {
+        self.push(data);
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        self.queue.dequeue()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.queue.peek()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.queue.peek_mut()
+    }
+
+    fn count(&self) -> usize {
+        self.queue.count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl<T> Index<usize> for CircularBuffer<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("CircularBuffer index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for CircularBuffer<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index)
+            .expect("CircularBuffer index out of bounds")
+    }
+}
+
+impl<T: Clone> Clone for CircularBuffer<T> {
+    fn clone(&self) -> Self {
+        let mut new_buffer = CircularBuffer::new(self.capacity);
+        new_buffer.extend(self.iter().cloned());
+        new_buffer.overwrite_count = self.overwrite_count;
+        new_buffer
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CircularBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> IntoIterator for CircularBuffer<T> {
+    type Item = T;
+    type IntoIter = RingQueueIntoIter<T>;
+
+    fn into_iter(self) -> RingQueueIntoIter<T> {
+        self.queue.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CircularBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = RingQueueIter<'a, T>;
+
+    fn into_iter(self) -> RingQueueIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut CircularBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = RingQueueIterMut<'a, T>;
+
+    fn into_iter(self) -> RingQueueIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> Extend<T> for CircularBuffer<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.push(data);
+        }
+    }
+}
+
+/// A `Queue` that dequeues by priority instead of arrival order. `key` gives
+/// the priority of each queued element; a min heap dequeues the lowest first
+/// and a max heap the highest. Elements of equal priority come out in no
+/// particular order.
+pub struct PriorityQueue<T, K, H = BinaryMinHeap<T>> {
+    heap: H,
+    key: K,
+    _marker: PhantomData<T>,
+}
+
+impl<T, K: Fn(&T) -> usize> PriorityQueue<T, K, BinaryMinHeap<T>> {
+    pub fn min_by_key(key: K) -> Self {
+        PriorityQueue::from_heap(BinaryMinHeap::new(None), key)
+    }
+}
+
+impl<T, K: Fn(&T) -> usize> PriorityQueue<T, K, BinaryMaxHeap<T>> {
+    pub fn max_by_key(key: K) -> Self {
+        PriorityQueue::from_heap(BinaryMaxHeap::new(None), key)
+    }
+}
+
+impl<T, K: Fn(&T) -> usize, H: BinaryHeap<T>> PriorityQueue<T, K, H> {
+    pub fn from_heap(heap: H, key: K) -> Self {
+        PriorityQueue {
+            heap,
+            key,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Queues `data` with an explicit priority instead of the one `key` gives.
+    pub fn queue_with_priority(&mut self, data: T, priority: usize) {
+        self.heap.insert(data, priority);
+    }
+}
+
+impl<T, K: Fn(&T) -> usize, H: BinaryHeap<T>> Queue<T> for PriorityQueue<T, K, H> {
+    fn queue(&mut self, data: T) {
+        let priority = (self.key)(&data);
+        self.heap.insert(data, priority);
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        self.heap.extract().ok()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.heap.peek().ok()
+    }
+
+    /// Changing the element does not change its place in the queue.
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.heap.peek_mut().ok()
+    }
+
+    fn count(&self) -> usize {
+        self.heap.size()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+/// Adapts a `LinkedList` to the `Queue` and `Deque` traits. Indexing walks the
+/// list from whichever end is nearer, so it is O(n).
+pub struct ListDeque<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> ListDeque<T> {
+    pub fn new() -> Self {
+        ListDeque {
+            list: LinkedList::new(),
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.list.get_mut(index)
+    }
+
+    pub fn into_inner(self) -> LinkedList<T> {
+        self.list
+    }
+}
+
+impl<T> Default for ListDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<LinkedList<T>> for ListDeque<T> {
+    fn from(list: LinkedList<T>) -> Self {
+        ListDeque { list }
+    }
+}
+
+impl<T> Queue<T> for ListDeque<T> {
+    fn queue(&mut self, data: T) {
+        self.list.insert_last(data);
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        self.list.remove_first().ok()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.list.get(0)
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list.get_mut(0)
+    }
+
+    fn count(&self) -> usize {
+        self.list.count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+}
+
+impl<T> Deque<T> for ListDeque<T> {
+    fn push_front(&mut self, data: T) {
+        self.list.insert_first(data);
+    }
+
+    fn push_back(&mut self, data: T) {
+        self.list.insert_last(data);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        self.list.remove_first().ok()
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        self.list.remove_last().ok()
+    }
+
+    fn front(&self) -> Option<&T> {
+        self.list.get(0)
+    }
+
+    fn back(&self) -> Option<&T> {
+        self.list
+            .count()
+            .checked_sub(1)
+            .and_then(|index| self.list.get(index))
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.list.get(index)
+    }
+}
+
+impl<T> Index<usize> for ListDeque<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.list.get(index).expect("ListDeque index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for ListDeque<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.list
+            .get_mut(index)
+            .expect("ListDeque index out of bounds")
+    }
+}
+
+impl<T> FromIterator<T> for ListDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut new_deque = ListDeque::new();
+        for data in iter {
+            new_deque.push_back(data);
+        }
+        new_deque
+    }
+}
+
+/// A singly linked queue that keeps a pointer to its tail, so queueing,
+/// dequeueing and appending are all O(1) in the worst case.
+pub struct ListQueue<T> {
+    head_node: *mut ListQueueNode<T>,
+    tail_node: *mut ListQueueNode<T>,
+    count: usize,
+    _marker: PhantomData<Box<ListQueueNode<T>>>,
+}
+
+struct ListQueueNode<T> {
+    data: T,
+    next_node: *mut ListQueueNode<T>,
+}
+
+pub struct ListQueueIter<'a, T> {
+    next_node: *const ListQueueNode<T>,
+    remaining: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct ListQueueIterMut<'a, T> {
+    next_node: *mut ListQueueNode<T>,
+    remaining: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+pub struct ListQueueIntoIter<T>(ListQueue<T>);
+
+// SAFETY: the queue owns its nodes exactly as a `Box` chain would.
+unsafe impl<T: Send> Send for ListQueue<T> {}
+unsafe impl<T: Sync> Sync for ListQueue<T> {}
+
+impl<T> ListQueue<T> {
+    pub fn new() -> Self {
+        ListQueue {
+            head_node: ptr::null_mut(),
+            tail_node: ptr::null_mut(),
+            count: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> ListQueueIter<'_, T> {
+        ListQueueIter {
+            next_node: self.head_node,
+            remaining: self.count,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> ListQueueIterMut<'_, T> {
+        ListQueueIterMut {
+            next_node: self.head_node,
+            remaining: self.count,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Moves every element of `other` to the back of this queue, leaving
+    /// `other` empty.
+    pub fn append(&mut self, other: &mut ListQueue<T>) {
+        if other.head_node.is_null() {
+            return;
+        }
+        if self.tail_node.is_null() {
+            self.head_node = other.head_node;
+        } else {
+            // SAFETY: a non-null tail points at a node owned by this queue.
+            unsafe { (*self.tail_node).next_node = other.head_node };
+        }
+        self.tail_node = other.tail_node;
+        self.count += other.count;
+        other.head_node = ptr::null_mut();
+        other.tail_node = ptr::null_mut();
+        other.count = 0;
+    }
+}
+
+impl<T> Default for ListQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Queue<T> for ListQueue<T> {
+    fn queue(&mut self, data: T) {
+        let new_node = Box::into_raw(Box::new(ListQueueNode {
+            data,
+            next_node: ptr::null_mut(),
+        }));
+        if self.tail_node.is_null() {
+            self.head_node = new_node;
+        } else {
+            // SAFETY: a non-null tail points at a node owned by this queue.
+            unsafe { (*self.tail_node).next_node = new_node };
+        }
+        self.tail_node = new_node;
+        self.count += 1;
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        if self.head_node.is_null() {
+            return None;
+        }
+        // SAFETY: the head came from `Box::into_raw` and is unlinked here.
+        let old_head = unsafe { Box::from_raw(self.head_node) };
+        self.head_node = old_head.next_node;
+        if self.head_node.is_null() {
+            self.tail_node = ptr::null_mut();
+        }
+        self.count -= 1;
+        Some(old_head.data)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        // SAFETY: a non-null head points at a node owned by this queue.
+        unsafe { self.head_node.as_ref() }.map(|node| &node.data)
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as in `peek`, and `&mut self` makes the borrow unique.
+        unsafe { self.head_node.as_mut() }.map(|node| &mut node.data)
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+impl<T> Drop for ListQueue<T> {
+    fn drop(&mut self) {
+        while self.dequeue().is_some() {}
+    }
+}
+
+impl<T: Clone> Clone for ListQueue<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ListQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T> Iterator for ListQueueIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        // SAFETY: the iterator borrows the queue, so its nodes stay alive.
+        unsafe { self.next_node.as_ref() }.map(|node| {
+            self.next_node = node.next_node;
+            self.remaining -= 1;
+            &node.data
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> Iterator for ListQueueIterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        // SAFETY: the iterator borrows the queue mutably and yields each
+        // node at most once.
+        unsafe { self.next_node.as_mut() }.map(|node| {
+            self.next_node = node.next_node;
+            self.remaining -= 1;
+            &mut node.data
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> Iterator for ListQueueIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.dequeue()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.count, Some(self.0.count))
+    }
+}
+
+impl<T> IntoIterator for ListQueue<T> {
+    type Item = T;
+    type IntoIter = ListQueueIntoIter<T>;
+
+    fn into_iter(self) -> ListQueueIntoIter<T> {
+        ListQueueIntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ListQueue<T> {
+    type Item = &'a T;
+    type IntoIter = ListQueueIter<'a, T>;
+
+    fn into_iter(self) -> ListQueueIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ListQueue<T> {
+    type Item = &'a mut T;
+    type IntoIter = ListQueueIterMut<'a, T>;
+
+    fn into_iter(self) -> ListQueueIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for ListQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut new_queue = ListQueue::new();
+        new_queue.extend(iter);
+        new_queue
+    }
+}
+
+impl<T> Extend<T> for ListQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.queue(data);
+        }
+    }
+}
+
+/// A bounded queue shared between producer and consumer threads. Once closed,
+/// puts fail while takes keep draining whatever is left.
+#[cfg(feature = "std")]
+pub struct BlockingQueue<T> {
+    state: std::sync::Mutex<BlockingQueueState<T>>,
+    not_empty: std::sync::Condvar,
+    not_full: std::sync::Condvar,
+}
+
+#[cfg(feature = "std")]
+struct BlockingQueueState<T> {
+    queue: RingQueue<T>,
+    capacity: usize,
+    closed: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingQueueState<T> {
+    fn is_full(&self) -> bool {
+        self.queue.count() == self.capacity
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "BlockingQueue needs a capacity of at least 1");
+        BlockingQueue {
+            state: std::sync::Mutex::new(BlockingQueueState {
+                queue: RingQueue::with_capacity(capacity),
+                capacity,
+                closed: false,
+            }),
+            not_empty: std::sync::Condvar::new(),
+            not_full: std::sync::Condvar::new(),
+        }
+    }
+
+    /// Waits for room, failing with `Error::Closed` if the queue is closed.
+    pub fn put(&self, data: T) -> Result<(), Error> {
+        let state = self.state.lock().unwrap();
+        let state = self
+            .not_full
+            .wait_while(state, |state| state.is_full() && !state.closed)
+            .unwrap();
+        self.queue_locked(state, data)
+    }
+
+    /// Waits for an element, failing with `Error::Closed` once the queue is
+    /// closed and empty.
+    pub fn take(&self) -> Result<T, Error> {
+        let state = self.state.lock().unwrap();
+        let state = self
+            .not_empty
+            .wait_while(state, |state| state.queue.is_empty() && !state.closed)
+            .unwrap();
+        self.dequeue_locked(state)
+    }
+
+    /// Like `put`, but gives up with `Error::Timeout` after `timeout`.
+    pub fn offer(&self, data: T, timeout: std::time::Duration) -> Result<(), Error> {
+        let state = self.state.lock().unwrap();
+        let (state, wait) = self
+            .not_full
+            .wait_timeout_while(state, timeout, |state| state.is_full() && !state.closed)
+            .unwrap();
+        if wait.timed_out() && state.is_full() && !state.closed {
+            return Err(Error::Timeout);
+        }
+        self.queue_locked(state, data)
+    }
+
+    /// Like `take`, but gives up with `Error::Timeout` after `timeout`.
+    pub fn poll(&self, timeout: std::time::Duration) -> Result<T, Error> {
+        let state = self.state.lock().unwrap();
+        let (state, wait) = self
+            .not_empty
+            .wait_timeout_while(state, timeout, |state| {
+                state.queue.is_empty() && !state.closed
+            })
+            .unwrap();
+        if wait.timed_out() && state.queue.is_empty() && !state.closed {
+            return Err(Error::Timeout);
+        }
+        self.dequeue_locked(state)
+    }
+
+    /// Wakes every waiting thread. Later puts fail and takes fail once the
+    /// remaining elements have been drained.
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Moves every queued element onto the end of `target` without waiting,
+    /// returning how many were moved.
+    pub fn drain_to(&self, target: &mut Vec<T>) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let drained = state.queue.count();
+        target.reserve(drained);
+        while let Some(data) = state.queue.dequeue() {
+            target.push(data);
+        }
+        drop(state);
+        self.not_full.notify_all();
+        drained
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.state.lock().unwrap().capacity
+    }
+
+    pub fn count(&self) -> usize {
+        self.state.lock().unwrap().queue.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().queue.is_empty()
+    }
+
+    fn queue_locked(
+        &self,
+        mut state: std::sync::MutexGuard<'_, BlockingQueueState<T>>,
+        data: T,
+    ) -> Result<(), Error> {
+        if state.closed {
+            return Err(Error::Closed);
+        }
+        state.queue.queue(data);
+        drop(state);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    fn dequeue_locked(
+        &self,
+        mut state: std::sync::MutexGuard<'_, BlockingQueueState<T>>,
+    ) -> Result<T, Error> {
+        let data = state.queue.dequeue().ok_or(Error::Closed)?;
+        drop(state);
+        self.not_full.notify_one();
+        Ok(data)
+    }
+}
+
+/// A bounded queue for async code. `push` waits for room and `pop` waits for
+/// an element without blocking the thread; once closed, pushes fail while pops
+/// keep draining whatever is left.
+#[cfg(feature = "std")]
+pub struct AsyncQueue<T> {
+    state: std::sync::Mutex<AsyncQueueState<T>>,
+}
+
+#[cfg(feature = "std")]
+struct AsyncQueueState<T> {
+    queue: RingQueue<T>,
+    capacity: usize,
+    closed: bool,
+    pushers: WaiterQueue,
+    poppers: WaiterQueue,
+}
+
+// Tasks waiting on an `AsyncQueue`, woken in the order they first waited.
+// Each queued waiter holds a slot whose generation changes once it is woken or
+// removed, so `order` may keep stale keys that are skipped when waking.
+#[cfg(feature = "std")]
+struct WaiterQueue {
+    slots: Vec<WaiterSlot>,
+    free_slots: Vec<usize>,
+    order: RingQueue<WaiterKey>,
+    queued: usize,
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Copy)]
+struct WaiterKey {
+    slot: usize,
+    generation: usize,
+}
+
+#[cfg(feature = "std")]
+struct WaiterSlot {
+    generation: usize,
+    waker: Option<core::task::Waker>,
+}
+
+#[cfg(feature = "std")]
+pub struct AsyncQueuePush<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    data: Option<T>,
+    waiter: Option<WaiterKey>,
+}
+
+#[cfg(feature = "std")]
+pub struct AsyncQueuePop<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    waiter: Option<WaiterKey>,
+}
+
+#[cfg(feature = "std")]
+impl WaiterQueue {
+    fn new() -> Self {
+        WaiterQueue {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            order: RingQueue::new(),
+            queued: 0,
+        }
+    }
+
+    // Updates the waker of an already queued waiter, or queues it again if it
+    // has been woken since.
+    fn register(&mut self, waiter: &mut Option<WaiterKey>, waker: &core::task::Waker) {
+        if let Some(queued_waker) = waiter.and_then(|key| self.queued_waker(key)) {
+            queued_waker.clone_from(waker);
+            return;
+        }
+        let slot = self.free_slots.pop().unwrap_or_else(|| {
+            self.slots.push(WaiterSlot {
+                generation: 0,
+                waker: None,
+            });
+            self.slots.len() - 1
+        });
+        self.slots[slot].waker = Some(waker.clone());
+        let key = WaiterKey {
+            slot,
+            generation: self.slots[slot].generation,
+        };
+        self.order.queue(key);
+        self.queued += 1;
+        *waiter = Some(key);
+    }
+
+    // Returns false if the waiter was not queued, because it never waited or
+    // has already been woken.
+    fn remove(&mut self, waiter: Option<WaiterKey>) -> bool {
+        let removed = waiter.and_then(|key| self.take(key)).is_some();
+        // Sweeping out stale keys once they outnumber the queued ones keeps
+        // `order` within twice its live length at O(1) amortised cost.
+        if removed && self.order.count() > 2 * self.queued + 8 {
+            for _ in 0..self.order.count() {
+                if let Some(key) = self.order.dequeue() {
+                    if self.queued_waker(key).is_some() {
+                        self.order.queue(key);
+                    }
+                }
+            }
+        }
+        removed
+    }
+
+    // Forgets a dropped waiter. One that was woken but never got to act passes
+    // its wakeup on to the next waiter.
+    fn cancel(&mut self, waiter: Option<WaiterKey>) {
+        if waiter.is_some() && !self.remove(waiter) {
+            self.wake_one();
+        }
+    }
+
+    fn wake_one(&mut self) {
+        while let Some(key) = self.order.dequeue() {
+            if let Some(waker) = self.take(key) {
+                waker.wake();
+                return;
+            }
+        }
+    }
+
+    fn wake_all(&mut self) {
+        while let Some(key) = self.order.dequeue() {
+            if let Some(waker) = self.take(key) {
+                waker.wake();
+            }
+        }
+    }
+
+    fn queued_waker(&mut self, key: WaiterKey) -> Option<&mut core::task::Waker> {
+        self.slots
+            .get_mut(key.slot)
+            .filter(|slot| slot.generation == key.generation)
+            .and_then(|slot| slot.waker.as_mut())
+    }
+
+    // Dequeues the waiter in place, freeing its slot for the next one.
+    fn take(&mut self, key: WaiterKey) -> Option<core::task::Waker> {
+        let slot = self
+            .slots
+            .get_mut(key.slot)
+            .filter(|slot| slot.generation == key.generation)?;
+        let waker = slot.waker.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(key.slot);
+        self.queued -= 1;
+        Some(waker)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> AsyncQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "AsyncQueue needs a capacity of at least 1");
+        AsyncQueue {
+            state: std::sync::Mutex::new(AsyncQueueState {
+                queue: RingQueue::with_capacity(capacity),
+                capacity,
+                closed: false,
+                pushers: WaiterQueue::new(),
+                poppers: WaiterQueue::new(),
+            }),
+        }
+    }
+
+    /// Resolves once `data` is queued, or to `Error::Closed` if the queue is
+    /// closed first.
+    pub fn push(&self, data: T) -> AsyncQueuePush<'_, T> {
+        AsyncQueuePush {
+            queue: self,
+            data: Some(data),
+            waiter: None,
+        }
+    }
+
+    /// Resolves to the front element, or to `Error::Closed` once the queue is
+    /// closed and empty.
+    pub fn pop(&self) -> AsyncQueuePop<'_, T> {
+        AsyncQueuePop {
+            queue: self,
+            waiter: None,
+        }
+    }
+
+    /// Wakes every waiting task. Later pushes fail and pops fail once the
+    /// remaining elements have been drained.
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.pushers.wake_all();
+        state.poppers.wake_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.state.lock().unwrap().capacity
+    }
+
+    pub fn count(&self) -> usize {
+        self.state.lock().unwrap().queue.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().queue.is_empty()
+    }
+}
+
+// Neither future hands out pinned references to its fields.
+#[cfg(feature = "std")]
+impl<T> Unpin for AsyncQueuePush<'_, T> {}
+
+#[cfg(feature = "std")]
+impl<T> core::future::Future for AsyncQueuePush<'_, T> {
+    type Output = Result<(), Error>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        context: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.queue.state.lock().unwrap();
+        if state.closed {
+            state.pushers.remove(this.waiter.take());
+            return core::task::Poll::Ready(Err(Error::Closed));
+        }
+        if state.queue.count() == state.capacity {
+            state.pushers.register(&mut this.waiter, context.waker());
+            return core::task::Poll::Pending;
+        }
+        let data = this
+            .data
+            .take()
+            .expect("AsyncQueuePush polled after completion");
+        state.queue.queue(data);
+        state.pushers.remove(this.waiter.take());
+        state.poppers.wake_one();
+        core::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for AsyncQueuePush<'_, T> {
+    fn drop(&mut self) {
+        if self.waiter.is_some() {
+            let waiter = self.waiter.take();
+            self.queue.state.lock().unwrap().pushers.cancel(waiter);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> core::future::Future for AsyncQueuePop<'_, T> {
+    type Output = Result<T, Error>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        context: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.queue.state.lock().unwrap();
+        match state.queue.dequeue() {
+            Some(data) => {
+                state.poppers.remove(this.waiter.take());
+                state.pushers.wake_one();
+                core::task::Poll::Ready(Ok(data))
+            }
+            None if state.closed => {
+                state.poppers.remove(this.waiter.take());
+                core::task::Poll::Ready(Err(Error::Closed))
+            }
+            None => {
+                state.poppers.register(&mut this.waiter, context.waker());
+                core::task::Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for AsyncQueuePop<'_, T> {
+    fn drop(&mut self) {
+        if self.waiter.is_some() {
+            let waiter = self.waiter.take();
+            self.queue.state.lock().unwrap().poppers.cancel(waiter);
+        }
+    }
+}
+
+// Keeps the producer and consumer indices on separate cache lines so the two
+// threads do not invalidate each other's line on every operation.
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+struct SpscBuffer<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    mask: usize,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+}
+
+/// The sending half of a queue made by `spsc_queue`.
+pub struct SpscProducer<T> {
+    shared: Arc<SpscBuffer<T>>,
+    tail: usize,
+    cached_head: usize,
+}
+
+/// The receiving half of a queue made by `spsc_queue`.
+pub struct SpscConsumer<T> {
+    shared: Arc<SpscBuffer<T>>,
+    head: usize,
+    cached_tail: usize,
+}
+
+// SAFETY: each half only touches the slots it currently owns, handing them
+// over through the release/acquire indices, so moving a half to another
+// thread only moves `T`s between threads.
+unsafe impl<T: Send> Send for SpscProducer<T> {}
+unsafe impl<T: Send> Send for SpscConsumer<T> {}
+
+/// A wait-free single-producer single-consumer ring queue. The capacity is
+/// rounded up to a power of two.
+pub fn spsc_queue<T>(capacity: usize) -> (SpscProducer<T>, SpscConsumer<T>) {
+    assert!(capacity > 0, "spsc_queue needs a capacity of at least 1");
+    let capacity = capacity.next_power_of_two();
+    let shared = Arc::new(SpscBuffer {
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        mask: capacity - 1,
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(AtomicUsize::new(0)),
+    });
+    (
+        SpscProducer {
+            shared: shared.clone(),
+            tail: 0,
+            cached_head: 0,
+        },
+        SpscConsumer {
+            shared,
+            head: 0,
+            cached_tail: 0,
+        },
+    )
+}
+
+impl<T> SpscBuffer<T> {
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        self.buffer[index & self.mask].get()
+    }
+}
+
+// Publishes a half's local index when a batch operation ends, including when a
+// user `clone` or `drop` unwinds out of it part way through.
+struct SpscProgress<'a>(&'a AtomicUsize, &'a mut usize);
+
+impl Drop for SpscProgress<'_> {
+    fn drop(&mut self) {
+        self.0.store(*self.1, Ordering::Release);
+    }
+}
+
+impl<T> Drop for SpscBuffer<T> {
+    fn drop(&mut self) {
+        let (head, tail) = (*self.head.0.get_mut(), *self.tail.0.get_mut());
+        for index in 0..tail.wrapping_sub(head) {
+            // SAFETY: slots from head to tail are initialised and both halves
+            // are gone, so nothing else can read them.
+            unsafe { (*self.slot(head.wrapping_add(index))).assume_init_drop() };
+        }
+    }
+}
+
+impl<T> SpscProducer<T> {
+    /// Hands `data` back if the queue is full.
+    pub fn push(&mut self, data: T) -> Result<(), T> {
+        if self.free_slots() == 0 {
+            return Err(data);
+        }
+        // SAFETY: the slot at tail is free and only the producer writes it.
+        unsafe { (*self.shared.slot(self.tail)).write(data) };
+        self.tail = self.tail.wrapping_add(1);
+        self.shared.tail.0.store(self.tail, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pushes as many elements from the front of `data` as fit, returning how
+    /// many were pushed.
+    pub fn push_slice(&mut self, data: &[T]) -> usize
+    where
+        T: Clone,
+    {
+        let pushed = data.len().min(self.free_slots());
+        let progress = SpscProgress(&self.shared.tail.0, &mut self.tail);
+        for data in &data[..pushed] {
+            let data = data.clone();
+            // SAFETY: as in `push`; the slot is only published once the guard
+            // stores the advanced tail.
+            unsafe { (*self.shared.slot(*progress.1)).write(data) };
+            *progress.1 = progress.1.wrapping_add(1);
+        }
+        pushed
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.tail
+            .wrapping_sub(self.shared.head.0.load(Ordering::Acquire))
+            == self.shared.capacity()
+    }
+
+    /// Only refreshes the consumer's index once the cached copy says the
+    /// queue is full.
+    fn free_slots(&mut self) -> usize {
+        let capacity = self.shared.capacity();
+        if self.tail.wrapping_sub(self.cached_head) == capacity {
+            self.cached_head = self.shared.head.0.load(Ordering::Acquire);
+        }
+        capacity - self.tail.wrapping_sub(self.cached_head)
+    }
+}
+
+impl<T> SpscConsumer<T> {
+    pub fn pop(&mut self) -> Option<T> {
+        if self.available() == 0 {
+            return None;
+        }
+        // SAFETY: the slot at head was published by the producer and is
+        // released back to it only by the store below.
+        let data = unsafe { (*self.shared.slot(self.head)).assume_init_read() };
+        self.head = self.head.wrapping_add(1);
+        self.shared.head.0.store(self.head, Ordering::Release);
+        Some(data)
+    }
+
+    /// Pops into the front of `target` until either runs out, returning how
+    /// many elements were popped.
+    pub fn pop_slice(&mut self, target: &mut [T]) -> usize {
+        let popped = target.len().min(self.available());
+        let progress = SpscProgress(&self.shared.head.0, &mut self.head);
+        for target in &mut target[..popped] {
+            // SAFETY: as in `pop`; head moves past the slot before the old
+            // target is dropped, so a panicking drop can not read it twice.
+            let data = unsafe { (*self.shared.slot(*progress.1)).assume_init_read() };
+            *progress.1 = progress.1.wrapping_add(1);
+            *target = data;
+        }
+        popped
+    }
+
+    pub fn peek(&mut self) -> Option<&T> {
+        if self.available() == 0 {
+            return None;
+        }
+        // SAFETY: the slot is published and can not be reused until `pop`.
+        Some(unsafe { (*self.shared.slot(self.head)).assume_init_ref() })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shared.tail.0.load(Ordering::Acquire) == self.head
+    }
+
+    /// Only refreshes the producer's index once the cached copy says the
+    /// queue is empty.
+    fn available(&mut self) -> usize {
+        if self.cached_tail == self.head {
+            self.cached_tail = self.shared.tail.0.load(Ordering::Acquire);
+        }
+        self.cached_tail.wrapping_sub(self.head)
+    }
+}
+
+/// An unbounded lock-free Michael-Scott queue. The head is always a dummy node
+/// whose successor holds the front element.
+pub struct ConcurrentQueue<T> {
+    head_node: AtomicPtr<ConcurrentQueueNode<T>>,
+    tail_node: AtomicPtr<ConcurrentQueueNode<T>>,
+    count: AtomicUsize,
+    hazards: HazardDomain,
+    _marker: PhantomData<T>,
+}
+
+struct ConcurrentQueueNode<T> {
+    data: MaybeUninit<T>,
+    next_node: AtomicPtr<ConcurrentQueueNode<T>>,
+}
+
+unsafe impl<T: Send> Sync for ConcurrentQueue<T> {}
+
+impl<T> ConcurrentQueueNode<T> {
+    fn new(data: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(ConcurrentQueueNode {
+            data,
+            next_node: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+impl<T> ConcurrentQueue<T> {
+    pub fn new() -> Self {
+        let dummy_node = ConcurrentQueueNode::new(MaybeUninit::uninit());
+        ConcurrentQueue {
+            head_node: AtomicPtr::new(dummy_node),
+            tail_node: AtomicPtr::new(dummy_node),
+            count: AtomicUsize::new(0),
+            hazards: HazardDomain::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for ConcurrentQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SharedQueue<T> for ConcurrentQueue<T> {
+    fn queue(&self, data: T) {
+        let new_node = ConcurrentQueueNode::new(MaybeUninit::new(data));
+        let hazard = self.hazards.acquire();
+        self.count.fetch_add(1, Ordering::Relaxed);
+        loop {
+            // A node is only retired after the head has moved past it, and
+            // the head never overtakes the tail, so a protected tail is live.
+            let tail_node = hazard.protect(&self.tail_node);
+            // SAFETY: the hazard keeps the tail node from being freed.
+            let next_node = unsafe { (*tail_node).next_node.load(Ordering::SeqCst) };
+            if !next_node.is_null() {
+                // Another enqueue linked its node but has not swung the tail yet.
+                let _ = self.tail_node.compare_exchange(
+                    tail_node,
+                    next_node,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+                continue;
+            }
+            // SAFETY: as above.
+            let linked = unsafe {
+                (*tail_node).next_node.compare_exchange(
+                    ptr::null_mut(),
+                    new_node,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+            };
+            if linked.is_ok() {
+                let _ = self.tail_node.compare_exchange(
+                    tail_node,
+                    new_node,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+                return;
+            }
+        }
+    }
+
+    fn dequeue(&self) -> Option<T> {
+        let head_hazard = self.hazards.acquire();
+        let next_hazard = self.hazards.acquire();
+        loop {
+            let head_node = head_hazard.protect(&self.head_node);
+            // SAFETY: the hazard keeps the head node from being freed.
+            let next_node = next_hazard.protect(unsafe { &(*head_node).next_node });
+            // The successor is only safe to use if it was read while the
+            // protected node was still the head.
+            if self.head_node.load(Ordering::SeqCst) != head_node {
+                continue;
+            }
+            if next_node.is_null() {
+                return None;
+            }
+            let tail_node = self.tail_node.load(Ordering::SeqCst);
+            if tail_node == head_node {
+                let _ = self.tail_node.compare_exchange(
+                    tail_node,
+                    next_node,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+                continue;
+            }
+            if self
+                .head_node
+                .compare_exchange(head_node, next_node, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                self.count.fetch_sub(1, Ordering::Relaxed);
+                // SAFETY: winning the exchange makes this thread the only one
+                // to take the successor's data, which becomes the new dummy,
+                // and the only one to retire the unlinked old head.
+                let data = unsafe { (*next_node).data.assume_init_read() };
+                head_hazard.clear();
+                next_hazard.clear();
+                unsafe { self.hazards.retire(head_node) };
+                return Some(data);
+            }
+        }
+    }
+
+    /// A snapshot that may already be stale while other threads queue or
+    /// dequeue.
+    fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    fn is_empty(&self) -> bool {
+        let head_hazard = self.hazards.acquire();
+        let head_node = head_hazard.protect(&self.head_node);
+        // SAFETY: the hazard keeps the head node from being freed.
+        unsafe { (*head_node).next_node.load(Ordering::SeqCst).is_null() }
+    }
+}
+
+impl<T> Drop for ConcurrentQueue<T> {
+    fn drop(&mut self) {
+        // SAFETY: with `&mut self` every remaining node is owned by the queue;
+        // only the nodes after the dummy hold data.
+        let dummy_node = unsafe { Box::from_raw(*self.head_node.get_mut()) };
+        let mut current_node = dummy_node.next_node.load(Ordering::Relaxed);
+        while !current_node.is_null() {
+            let mut node = unsafe { Box::from_raw(current_node) };
+            unsafe { node.data.assume_init_drop() };
+            current_node = *node.next_node.get_mut();
+        }
+    }
+}
+
+/// An immutable queue where every operation returns a new version that shares
+/// structure with the old one. This is Hood and Melville's real-time queue:
+/// the rear list is reversed onto the front a few steps at a time on every
+/// operation, so queueing and dequeueing are O(1) in the worst case, even
+/// when old versions are reused.
+pub struct PersistentQueue<T> {
+    front_count: usize,
+    front: PersistentStack<Rc<T>>,
+    rotation: Rotation<T>,
+    rear_count: usize,
+    rear: PersistentStack<Rc<T>>,
+}
+
+// A rotation rebuilds the front as `front ++ reverse(rear)` by reversing both
+// lists in lockstep and then appending the reversed front onto the reversed
+// rear. `kept` counts the reversed front elements that have not since been
+// dequeued from the old front, and so still belong in the result.
+enum Rotation<T> {
+    Idle,
+    Reversing {
+        kept: usize,
+        front: PersistentStack<Rc<T>>,
+        reversed_front: PersistentStack<Rc<T>>,
+        rear: PersistentStack<Rc<T>>,
+        reversed_rear: PersistentStack<Rc<T>>,
+    },
+    Appending {
+        kept: usize,
+        reversed_front: PersistentStack<Rc<T>>,
+        result: PersistentStack<Rc<T>>,
+    },
+    Done(PersistentStack<Rc<T>>),
+}
+
+impl<T> Rotation<T> {
+    fn step(&self) -> Self {
+        match self {
+            Rotation::Reversing {
+                kept,
+                front,
+                reversed_front,
+                rear,
+                reversed_rear,
+            } => {
+                let rear_top = rear.peek().expect("rotation rear outlasts its front");
+                match front.peek() {
+                    Some(front_top) => Rotation::Reversing {
+                        kept: kept + 1,
+                        front: front.pop().unwrap_or_default(),
+                        reversed_front: reversed_front.push(front_top.clone()),
+                        rear: rear.pop().unwrap_or_default(),
+                        reversed_rear: reversed_rear.push(rear_top.clone()),
+                    },
+                    None => Rotation::Appending {
+                        kept: *kept,
+                        reversed_front: reversed_front.clone(),
+                        result: reversed_rear.push(rear_top.clone()),
+                    },
+                }
+            }
+            Rotation::Appending {
+                kept: 0, result, ..
+            } => Rotation::Done(result.clone()),
+            Rotation::Appending {
+                kept,
+                reversed_front,
+                result,
+            } => {
+                let front_top = reversed_front
+                    .peek()
+                    .expect("rotation keeps no more than it reversed");
+                Rotation::Appending {
+                    kept: kept - 1,
+                    reversed_front: reversed_front.pop().unwrap_or_default(),
+                    result: result.push(front_top.clone()),
+                }
+            }
+            other => other.clone(),
+        }
+    }
+
+    // Accounts for an element dequeued from the old front while the rotation
+    // is still copying it.
+    fn invalidate(&self) -> Self {
+        match self {
+            Rotation::Reversing {
+                kept,
+                front,
+                reversed_front,
+                rear,
+                reversed_rear,
+            } => Rotation::Reversing {
+                kept: kept - 1,
+                front: front.clone(),
+                reversed_front: reversed_front.clone(),
+                rear: rear.clone(),
+                reversed_rear: reversed_rear.clone(),
+            },
+            Rotation::Appending {
+                kept: 0, result, ..
+            } => Rotation::Done(result.pop().unwrap_or_default()),
+            Rotation::Appending {
+                kept,
+                reversed_front,
+                result,
+            } => Rotation::Appending {
+                kept: kept - 1,
+                reversed_front: reversed_front.clone(),
+                result: result.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+impl<T> Clone for Rotation<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Rotation::Idle => Rotation::Idle,
+            Rotation::Reversing {
+                kept,
+                front,
+                reversed_front,
+                rear,
+                reversed_rear,
+            } => Rotation::Reversing {
+                kept: *kept,
+                front: front.clone(),
+                reversed_front: reversed_front.clone(),
+                rear: rear.clone(),
+                reversed_rear: reversed_rear.clone(),
+            },
+            Rotation::Appending {
+                kept,
+                reversed_front,
+                result,
+            } => Rotation::Appending {
+                kept: *kept,
+                reversed_front: reversed_front.clone(),
+                result: result.clone(),
+            },
+            Rotation::Done(result) => Rotation::Done(result.clone()),
+        }
+    }
+}
+
+impl<T> PersistentQueue<T> {
+    pub fn new() -> Self {
+        PersistentQueue {
+            front_count: 0,
+            front: PersistentStack::new(),
+            rotation: Rotation::Idle,
+            rear_count: 0,
+            rear: PersistentStack::new(),
+        }
+    }
+
+    /// Returns a new version with `data` at the back, sharing `self`.
+    pub fn queue(&self, data: T) -> Self {
+        Self::balance(
+            self.front_count,
+            self.front.clone(),
+            self.rotation.clone(),
+            self.rear_count + 1,
+            self.rear.push(Rc::new(data)),
+        )
+    }
+
+    /// Returns a new version without the front element, or `None` if `self`
+    /// is empty.
+    pub fn dequeue(&self) -> Option<Self> {
+        let front = self.front.pop()?;
+        Some(Self::balance(
+            self.front_count - 1,
+            front,
+            self.rotation.invalidate(),
+            self.rear_count,
+            self.rear.clone(),
+        ))
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.front.peek().map(|data| &**data)
+    }
+
+    pub fn count(&self) -> usize {
+        self.front_count + self.rear_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front_count == 0
+    }
+
+    // Starts a rotation once the rear outgrows the front, then advances any
+    // rotation in progress by two steps, which is enough for it to finish
+    // before the new front runs out.
+    fn balance(
+        front_count: usize,
+        front: PersistentStack<Rc<T>>,
+        rotation: Rotation<T>,
+        rear_count: usize,
+        rear: PersistentStack<Rc<T>>,
+    ) -> Self {
+        let (front_count, rotation, rear_count, rear) = if rear_count <= front_count {
+            (front_count, rotation, rear_count, rear)
+        } else {
+            let rotation = Rotation::Reversing {
+                kept: 0,
+                front: front.clone(),
+                reversed_front: PersistentStack::new(),
+                rear,
+                reversed_rear: PersistentStack::new(),
+            };
+            (
+                front_count + rear_count,
+                rotation,
+                0,
+                PersistentStack::new(),
+            )
+        };
+        match rotation.step().step() {
+            Rotation::Done(front) => PersistentQueue {
+                front_count,
+                front,
+                rotation: Rotation::Idle,
+                rear_count,
+                rear,
+            },
+            rotation => PersistentQueue {
+                front_count,
+                front,
+                rotation,
+                rear_count,
+                rear,
+            },
+        }
+    }
+}
+
+impl<T> Default for PersistentQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for PersistentQueue<T> {
+    fn clone(&self) -> Self {
+        PersistentQueue {
+            front_count: self.front_count,
+            front: self.front.clone(),
+            rotation: self.rotation.clone(),
+            rear_count: self.rear_count,
+            rear: self.rear.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut version = self.clone();
+        while let Some(data) = version.front.peek().cloned() {
+            list.entry(&data);
+            version = version.dequeue().unwrap_or_default();
+        }
+        list.finish()
+    }
+}
+
+impl<T> FromIterator<T> for PersistentQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentQueue::new(), |queue, data| queue.queue(data))
+    }
+}
+
+/// A queue built from two stacks. Elements are pushed onto the inbox and
+/// popped from the outbox, which is refilled by reversing the inbox whenever it
+/// runs dry, so every operation is amortised O(1). With a bounded `S` such as
+/// `ArrayStack`, use `try_queue`; `Queue::queue` panics once the inbox is full.
+pub struct StackQueue<T, S = VecStack<T>> {
+    inbox: S,
+    outbox: S,
+    _marker: PhantomData<T>,
+}
+
+impl<T, S: Stack<T> + Default> StackQueue<T, S> {
+    pub fn new() -> Self {
+        StackQueue {
+            inbox: S::default(),
+            outbox: S::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, S: Stack<T>> StackQueue<T, S> {
+    pub fn try_queue(&mut self, data: T) -> Result<(), Error> {
+        if self.outbox.is_empty() {
+            self.outbox.push(data)
+        } else {
+            self.inbox.push(data)
+        }
+    }
+
+    // The outbox is kept non-empty whenever the queue is, so the front of the
+    // queue is always the top of the outbox.
+    fn refill_outbox(&mut self) {
+        if self.outbox.is_empty() {
+            while let Some(data) = self.inbox.pop() {
+                // An empty outbox has room for everything its twin held.
+                self.outbox
+                    .push(data)
+                    .expect("StackQueue outbox is smaller than its inbox");
+            }
+        }
+    }
+}
+
+impl<T, S: Stack<T> + Default> Default for StackQueue<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S: Stack<T>> Queue<T> for StackQueue<T, S> {
+    fn queue(&mut self, data: T) {
+        self.try_queue(data)
+            .expect("can not queue onto a StackQueue whose stack is full");
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        let data = self.outbox.pop();
+        self.refill_outbox();
+        data
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.outbox.peek()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.outbox.peek_mut()
+    }
+
+    fn count(&self) -> usize {
+        self.inbox.count() + self.outbox.count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.outbox.is_empty()
+    }
+}
+
+/// A two-stack queue where each stack entry also stores the running aggregate
+/// of everything beneath it. `combine` must be associative but need not be
+/// commutative; the aggregate is taken over the queue from front to back.
+/// The bottom entry of each stack stores no aggregate, since it is just the
+/// entry itself, and the combined aggregate of both stacks is cached so that
+/// `aggregate` can hand out a reference.
+pub struct AggregateQueue<T, F> {
+    inbox: VecStack<(T, Option<T>)>,
+    outbox: VecStack<(T, Option<T>)>,
+    aggregate: Option<T>,
+    combine: F,
+}
+
+impl<T, F: Fn(&T, &T) -> T> AggregateQueue<T, F> {
+    pub fn new(combine: F) -> Self {
+        AggregateQueue {
+            inbox: VecStack::new(),
+            outbox: VecStack::new(),
+            aggregate: None,
+            combine,
+        }
+    }
+
+    pub fn queue(&mut self, data: T) {
+        if self.outbox.is_empty() {
+            self.outbox.push_unbounded((data, None));
+            return;
+        }
+        let aggregate = stack_aggregate(&self.inbox).map(|below| (self.combine)(below, &data));
+        self.inbox.push_unbounded((data, aggregate));
+        self.update_aggregate();
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        let (data, _) = self.outbox.pop()?;
+        if self.outbox.is_empty() {
+            while let Some((data, _)) = self.inbox.pop() {
+                let aggregate =
+                    stack_aggregate(&self.outbox).map(|below| (self.combine)(&data, below));
+                self.outbox.push_unbounded((data, aggregate));
+            }
+        }
+        self.update_aggregate();
+        Some(data)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.outbox.peek().map(|(data, _)| data)
+    }
+
+    pub fn aggregate(&self) -> Option<&T> {
+        self.aggregate
+            .as_ref()
+            .or_else(|| stack_aggregate(&self.outbox))
+    }
+
+    pub fn count(&self) -> usize {
+        self.inbox.count() + self.outbox.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outbox.is_empty()
+    }
+
+    // Only caches a value while both stacks hold entries; otherwise the
+    // outbox aggregate already covers the whole queue.
+    fn update_aggregate(&mut self) {
+        self.aggregate = match (stack_aggregate(&self.outbox), stack_aggregate(&self.inbox)) {
+            (Some(front), Some(back)) => Some((self.combine)(front, back)),
+            _ => None,
+        };
+    }
+}
+
+fn stack_aggregate<T>(stack: &VecStack<(T, Option<T>)>) -> Option<&T> {
+    stack
+        .peek()
+        .map(|(data, aggregate)| aggregate.as_ref().unwrap_or(data))
+}
+
+pub struct MinQueue<T>(AggregateQueue<T, fn(&T, &T) -> T>);
+
+impl<T: Ord + Clone> MinQueue<T> {
+    pub fn new() -> Self {
+        MinQueue(AggregateQueue::new(|a, b| a.min(b).clone()))
+    }
+
+    pub fn queue(&mut self, data: T) {
+        self.0.queue(data);
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.0.dequeue()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.0.peek()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.0.aggregate()
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: Ord + Clone> Default for MinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct MaxQueue<T>(AggregateQueue<T, fn(&T, &T) -> T>);
+
+impl<T: Ord + Clone> MaxQueue<T> {
+    pub fn new() -> Self {
+        MaxQueue(AggregateQueue::new(|a, b| a.max(b).clone()))
+    }
+
+    pub fn queue(&mut self, data: T) {
+        self.0.queue(data);
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.0.dequeue()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.0.peek()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.0.aggregate()
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: Ord + Clone> Default for MaxQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::{ArrayStack, ListStack};
+    use crate::test_data::PseudoRandom;
+
+    #[test]
+    fn shuffle_queue() {
+        let mut test_queue = ShuffleQueue::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+
+        test_queue.queue(test_data_1);
+        test_queue.queue(test_data_2);
+        test_queue.queue(test_data_3);
+
+        assert!(test_queue.dequeue().unwrap() == test_data_1);
+        assert!(*test_queue.peek().unwrap() == test_data_2);
+        assert!(test_queue.dequeue().unwrap() == test_data_2);
+        assert!(test_queue.dequeue().unwrap() == test_data_3);
+        assert!(test_queue.dequeue().is_none());
+    }
+
+    #[test]
+    fn shuffle_queue_peek_mut() {
+        let mut test_queue = ShuffleQueue::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+
+        assert!(test_queue.peek_mut().is_none());
+        test_queue.queue(test_data_1);
+        test_queue.queue(test_data_2);
+        *test_queue.peek_mut().unwrap() = test_data_3;
+
+        assert!(test_queue.dequeue().unwrap() == test_data_3);
+        assert!(test_queue.dequeue().unwrap() == test_data_2);
+    }
+
+    #[test]
+    fn shuffle_queue_try_dequeue_and_try_peek() {
+        let mut test_queue = ShuffleQueue::new();
+
+        assert!(test_queue.try_dequeue() == Err(Error::Empty));
+        assert!(test_queue.try_peek() == Err(Error::Empty));
+        test_queue.queue(10);
+        assert!(test_queue.try_peek() == Ok(&10));
+        assert!(test_queue.try_dequeue() == Ok(10));
+    }
+
+    #[test]
+    fn shuffle_queue_holds_non_clone_data() {
+        let mut test_queue: ShuffleQueue<Box<dyn Fn(i32) -> i32>> = ShuffleQueue::new();
+
+        test_queue.queue(Box::new(|x| x + 1));
+        test_queue.queue(Box::new(|x| x * 2));
+
+        assert!(test_queue.peek().unwrap()(10) == 11);
+        assert!(test_queue.dequeue().unwrap()(10) == 11);
+        assert!(test_queue.dequeue().unwrap()(10) == 20);
+    }
+
+    #[test]
+    fn ring_queue() {
+        let mut test_queue = RingQueue::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+
+        test_queue.queue(test_data_1);
+        test_queue.queue(test_data_2);
+        test_queue.queue(test_data_3);
+
+        assert!(test_queue.dequeue().unwrap() == test_data_1);
+        assert!(*test_queue.peek().unwrap() == test_data_2);
+        assert!(test_queue.dequeue().unwrap() == test_data_2);
+        assert!(test_queue.dequeue().unwrap() == test_data_3);
+        assert!(test_queue.dequeue().is_none());
+        assert!(test_queue.try_peek() == Err(Error::Empty));
+    }
+
+    #[test]
+    fn ring_queue_wraps_and_grows() {
+        let mut test_queue = RingQueue::with_capacity(4);
+
+        for data in 0..3 {
+            test_queue.queue(data);
+        }
+        assert!(test_queue.dequeue().unwrap() == 0);
+        assert!(test_queue.dequeue().unwrap() == 1);
+        for data in 3..6 {
+            test_queue.queue(data);
+        }
+        assert!(test_queue.capacity() == 4);
+        assert!(test_queue.as_slices() == (&[2, 3][..], &[4, 5][..]));
+        assert!(test_queue[0] == 2 && test_queue[3] == 5);
+        assert!(test_queue.get(4).is_none());
+
+        test_queue.queue(6);
+        assert!(test_queue.capacity() == 8);
+        assert!(test_queue.as_slices() == (&[2, 3, 4, 5, 6][..], &[][..]));
+        test_queue[1] = 30;
+        for data in test_queue.iter_mut() {
+            *data += 1;
+        }
+        assert!(test_queue.iter().copied().collect::<Vec<_>>() == vec![3, 31, 5, 6, 7]);
+        assert!(format!("{:?}", test_queue) == "[3, 31, 5, 6, 7]");
+
+        test_queue.shrink_to_fit();
+        assert!(test_queue.capacity() == 5);
+        assert!(test_queue.clone().into_iter().collect::<Vec<_>>() == vec![3, 31, 5, 6, 7]);
+    }
+
+    #[test]
+    fn ring_queue_matches_shuffle_queue() {
+        let mut test_ring_queue = RingQueue::new();
+        let mut test_shuffle_queue = ShuffleQueue::new();
+
+        for step in 0..1000usize {
+            if step % 3 == 2 {
+                assert!(test_ring_queue.dequeue() == test_shuffle_queue.dequeue());
+            } else {
+                test_ring_queue.queue(step);
+                test_shuffle_queue.queue(step);
+            }
+            assert!(test_ring_queue.peek() == test_shuffle_queue.peek());
+            assert!(test_ring_queue.count() == test_shuffle_queue.count());
+        }
+    }
+
+    #[test]
+    fn ring_queue_drops_remaining_data() {
+        let test_data = std::rc::Rc::new(());
+        let mut test_queue = RingQueue::with_capacity(2);
+
+        for _ in 0..5 {
+            test_queue.queue(test_data.clone());
+        }
+        drop(test_queue.dequeue());
+        drop(test_queue.dequeue());
+        test_queue.queue(test_data.clone());
+        assert!(std::rc::Rc::strong_count(&test_data) == 5);
+        drop(test_queue);
+        assert!(std::rc::Rc::strong_count(&test_data) == 1);
+    }
+
+    fn check_deque<D: Deque<i32>>(mut test_deque: D) {
+        assert!(test_deque.front().is_none() && test_deque.back().is_none());
+        assert!(test_deque.pop_back().is_none());
+
+        test_deque.push_back(2);
+        test_deque.push_front(1);
+        test_deque.push_back(3);
+        test_deque.push_front(0);
+        test_deque.queue(4);
+        assert!(test_deque.count() == 5);
+        assert!(test_deque.front() == Some(&0) && test_deque.back() == Some(&4));
+        assert!((0..5).all(|index| test_deque.get(index) == Some(&(index as i32))));
+        assert!(test_deque.get(5).is_none());
+
+        test_deque.rotate_left(2);
+        assert!(test_deque.front() == Some(&2) && test_deque.back() == Some(&1));
+        test_deque.rotate_right(7);
+        assert!(test_deque.front() == Some(&0) && test_deque.back() == Some(&4));
+
+        assert!(test_deque.pop_back() == Some(4));
+        assert!(test_deque.pop_front() == Some(0));
+        assert!(test_deque.dequeue() == Some(1));
+        assert!(test_deque.pop_back() == Some(3));
+        assert!(test_deque.pop_front() == Some(2));
+        assert!(test_deque.is_empty());
+        test_deque.rotate_left(3);
+    }
+
+    #[test]
+    fn ring_queue_deque() {
+        check_deque(RingQueue::new());
+
+        let mut test_deque = RingQueue::with_capacity(4);
+        test_deque.push_front(1);
+        test_deque.push_front(0);
+        test_deque.push_back(2);
+        assert!(test_deque.as_slices() == (&[0, 1][..], &[2][..]));
+        test_deque.push_front(-1);
+        test_deque.push_front(-2);
+        assert!(test_deque.capacity() == 8);
+        assert!(test_deque.iter().copied().collect::<Vec<_>>() == vec![-2, -1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn list_deque() {
+        check_deque(ListDeque::new());
+
+        let mut test_deque: ListDeque<_> = (0..6).collect();
+        test_deque[4] = 40;
+        *test_deque.peek_mut().unwrap() = 10;
+        assert!(test_deque[0] == 10 && test_deque[4] == 40);
+        assert!(
+            test_deque.into_inner().into_iter().collect::<Vec<_>>() == vec![10, 1, 2, 3, 40, 5]
+        );
+    }
+
+    #[test]
+    fn circular_buffer_overwrites_oldest() {
+        let mut test_buffer = CircularBuffer::new(3);
+
+        assert!(test_buffer.push(1).is_none());
+        assert!(test_buffer.push(2).is_none());
+        assert!(test_buffer.push(3).is_none());
+        assert!(test_buffer.is_full());
+        assert!(test_buffer.push(4) == Some(1));
+        test_buffer.queue(5);
+        assert!(test_buffer.count() == 3 && test_buffer.capacity() == 3);
+        assert!(test_buffer.overwrite_count() == 2);
+        assert!(test_buffer.iter().copied().collect::<Vec<_>>() == vec![3, 4, 5]);
+        assert!(test_buffer.as_slices() == (&[3][..], &[4, 5][..]));
+        assert!(test_buffer[0] == 3 && test_buffer[2] == 5);
+        assert!(test_buffer.get(3).is_none());
+
+        assert!(test_buffer.dequeue() == Some(3));
+        assert!(*test_buffer.peek().unwrap() == 4);
+        test_buffer.queue(6);
+        test_buffer[2] = 60;
+        assert!(test_buffer.snapshot() == vec![4, 5, 60]);
+        assert!(format!("{:?}", test_buffer) == "[4, 5, 60]");
+    }
+
+    #[test]
+    fn circular_buffer_extend_from_slice() {
+        let mut test_buffer = CircularBuffer::new(4);
+
+        test_buffer.extend_from_slice(&[1, 2]);
+        test_buffer.extend_from_slice(&[3, 4, 5]);
+        assert!(test_buffer.snapshot() == vec![2, 3, 4, 5]);
+        test_buffer.extend_from_slice(&(10..20).collect::<Vec<_>>());
+        assert!(test_buffer.snapshot() == vec![16, 17, 18, 19]);
+        assert!(test_buffer.overwrite_count() == 11);
+
+        let mut test_clone = test_buffer.clone();
+        test_clone.push(20);
+        assert!(test_clone.capacity() == 4 && test_clone.snapshot() == vec![17, 18, 19, 20]);
+        for data in &mut test_buffer {
+            *data += 1;
+        }
+        assert!(test_buffer.into_iter().collect::<Vec<_>>() == vec![17, 18, 19, 20]);
+    }
+
+    fn drain_queue<Q: Queue<(usize, &'static str)>>(mut test_queue: Q) -> Vec<&'static str> {
+        for task in [
+            (3, "low"),
+            (1, "urgent"),
+            (2, "normal"),
+            (1, "urgent again"),
+        ] {
+            test_queue.queue(task);
+        }
+        core::iter::from_fn(|| test_queue.dequeue().map(|(_, name)| name)).collect()
+    }
+
+    #[test]
+    fn priority_queue_switches_ordering() {
+        assert!(drain_queue(ShuffleQueue::new()) == ["low", "urgent", "normal", "urgent again"]);
+        assert!(
+            drain_queue(PriorityQueue::max_by_key(|task: &(usize, _)| task.0))[..2]
+                == ["low", "normal"]
+        );
+
+        let min_order = drain_queue(PriorityQueue::min_by_key(|task: &(usize, _)| task.0));
+        assert!(min_order[2..] == ["normal", "low"]);
+        assert!(min_order[..2].contains(&"urgent") && min_order[..2].contains(&"urgent again"));
+    }
+
+    #[test]
+    fn priority_queue() {
+        let mut test_queue = PriorityQueue::min_by_key(|data: &i32| data.unsigned_abs() as usize);
+
+        assert!(test_queue.peek().is_none());
+        assert!(test_queue.try_dequeue() == Err(Error::Empty));
+        test_queue.queue(-5);
+        test_queue.queue(3);
+        test_queue.queue_with_priority(100, 0);
+        test_queue.queue(-1);
+        assert!(test_queue.count() == 4);
+        assert!(*test_queue.peek().unwrap() == 100);
+        *test_queue.peek_mut().unwrap() = 200;
+
+        assert!(test_queue.dequeue() == Some(200));
+        assert!(test_queue.dequeue() == Some(-1));
+        test_queue.queue(2);
+        assert!(test_queue.dequeue() == Some(2));
+        assert!(test_queue.dequeue() == Some(3));
+        assert!(test_queue.dequeue() == Some(-5));
+        assert!(test_queue.is_empty());
+    }
+
+    #[test]
+    fn list_queue() {
+        let mut test_queue = ListQueue::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+
+        assert!(test_queue.peek().is_none());
+        test_queue.queue(test_data_1);
+        test_queue.queue(test_data_2);
+        assert!(test_queue.dequeue().unwrap() == test_data_1);
+        assert!(test_queue.dequeue().unwrap() == test_data_2);
+        assert!(test_queue.dequeue().is_none());
+
+        test_queue.queue(test_data_3);
+        *test_queue.peek_mut().unwrap() += 1;
+        assert!(*test_queue.peek().unwrap() == test_data_3 + 1);
+        assert!(test_queue.count() == 1);
+        assert!(test_queue.try_dequeue() == Ok(test_data_3 + 1));
+        assert!(test_queue.try_dequeue() == Err(Error::Empty));
+    }
+
+    #[test]
+    fn list_queue_append_and_iterate() {
+        let mut test_queue: ListQueue<_> = (0..3).collect();
+        let mut test_other: ListQueue<_> = (3..6).collect();
+        let mut test_empty = ListQueue::new();
+
+        test_queue.append(&mut test_other);
+        test_queue.append(&mut test_empty);
+        assert!(test_other.is_empty() && test_other.peek().is_none());
+        assert!(test_queue.count() == 6);
+        test_other.queue(10);
+        test_other.append(&mut test_queue);
+        assert!(test_queue.is_empty());
+
+        for data in test_other.iter_mut() {
+            *data *= 2;
+        }
+        assert!(test_other.iter().copied().collect::<Vec<_>>() == vec![20, 0, 2, 4, 6, 8, 10]);
+        assert!(format!("{:?}", test_other.clone()) == "[20, 0, 2, 4, 6, 8, 10]");
+        test_other.queue(12);
+        assert!(test_other.into_iter().skip(6).collect::<Vec<_>>() == vec![10, 12]);
+    }
+
+    #[test]
+    fn list_queue_drops_long_chain() {
+        let mut test_queue = ListQueue::new();
+
+        for data in 0..1_000_000 {
+            test_queue.queue(data);
+        }
+        assert!(test_queue.count() == 1_000_000);
+        drop(test_queue);
+    }
+
+    fn persistent_queue_contents(test_queue: &PersistentQueue<usize>) -> Vec<usize> {
+        let mut contents = Vec::new();
+        let mut version = test_queue.clone();
+        while let Some(&data) = version.peek() {
+            contents.push(data);
+            version = version.dequeue().unwrap();
+        }
+        assert!(contents.len() == test_queue.count());
+        contents
+    }
+
+    #[test]
+    fn persistent_queue_versions() {
+        let test_empty = PersistentQueue::new();
+        let test_version_1 = test_empty.queue(10);
+        let test_version_2 = test_version_1.queue(42);
+        let test_version_3 = test_version_2.dequeue().unwrap();
+        let test_branch = test_version_2.queue(59492957);
+
+        assert!(test_empty.is_empty() && test_empty.dequeue().is_none());
+        assert!(*test_version_1.peek().unwrap() == 10);
+        assert!(*test_version_2.peek().unwrap() == 10);
+        assert!(*test_version_3.peek().unwrap() == 42);
+        assert!(test_version_3.count() == 1);
+        assert!(persistent_queue_contents(&test_branch) == vec![10, 42, 59492957]);
+        assert!(persistent_queue_contents(&test_version_2) == vec![10, 42]);
+        assert!(format!("{:?}", test_branch) == "[10, 42, 59492957]");
+    }
+
+    #[test]
+    fn persistent_queue_matches_model_across_versions() {
+        let mut test_random = PseudoRandom::new();
+        let mut next_random = move |modulus: usize| test_random.next_below(modulus as u64) as usize;
+        let mut test_versions = vec![(PersistentQueue::new(), Vec::new())];
+
+        for step in 0..2000 {
+            let (test_queue, model) = test_versions[next_random(test_versions.len())].clone();
+            let next_version = if next_random(3) == 0 {
+                match test_queue.dequeue() {
+                    Some(next_queue) => (next_queue, model[1..].to_vec()),
+                    None => continue,
+                }
+            } else {
+                let mut next_model = model.clone();
+                next_model.push(step);
+                (test_queue.queue(step), next_model)
+            };
+            assert!(next_version.0.peek() == next_version.1.first());
+            assert!(next_version.0.count() == next_version.1.len());
+            test_versions.push(next_version);
+        }
+        for (test_queue, model) in test_versions.iter().step_by(50) {
+            assert!(persistent_queue_contents(test_queue) == *model);
+        }
+    }
+
+    #[test]
+    fn persistent_queue_drops_long_chain() {
+        let test_queue: PersistentQueue<usize> = (0..1_000_000).collect();
+
+        assert!(test_queue.count() == 1_000_000);
+        assert!(*test_queue.peek().unwrap() == 0);
+        drop(test_queue);
+    }
+
+    #[test]
+    fn stack_queue() {
+        let mut test_vec_queue: StackQueue<i32> = StackQueue::new();
+        let mut test_list_queue: StackQueue<i32, ListStack<i32>> = StackQueue::new();
+        let mut test_shuffle_queue = ShuffleQueue::new();
+
+        assert!(test_vec_queue.peek().is_none());
+        for step in 0..500 {
+            if step % 3 == 1 {
+                let data = test_shuffle_queue.dequeue();
+                assert!(test_vec_queue.dequeue() == data);
+                assert!(test_list_queue.dequeue() == data);
+            } else {
+                test_vec_queue.queue(step);
+                test_list_queue.queue(step);
+                test_shuffle_queue.queue(step);
+            }
+            assert!(test_vec_queue.peek() == test_shuffle_queue.peek());
+            assert!(test_list_queue.peek() == test_shuffle_queue.peek());
+            assert!(test_vec_queue.count() == test_shuffle_queue.count());
+        }
+
+        *test_vec_queue.peek_mut().unwrap() = -1;
+        assert!(test_vec_queue.dequeue() == Some(-1));
+        while test_list_queue.dequeue().is_some() {}
+        assert!(test_list_queue.is_empty() && test_list_queue.count() == 0);
+    }
+
+    #[test]
+    fn stack_queue_with_bounded_stack() {
+        let mut test_queue: StackQueue<i32, ArrayStack<i32, 2>> = StackQueue::new();
+
+        assert!(test_queue.try_queue(10).is_ok());
+        assert!(test_queue.try_queue(42).is_ok());
+        assert!(test_queue.try_queue(59492957).is_ok());
+        assert!(test_queue.try_queue(7) == Err(Error::CapacityExceeded));
+        assert!(test_queue.count() == 3);
+        assert!(test_queue.dequeue() == Some(10));
+        assert!(test_queue.try_queue(7).is_ok());
+        assert!(test_queue.try_queue(8).is_ok());
+        assert!(test_queue.try_queue(9) == Err(Error::CapacityExceeded));
+        assert!(test_queue.dequeue() == Some(42));
+        assert!(test_queue.dequeue() == Some(59492957));
+        assert!(test_queue.dequeue() == Some(7));
+        assert!(test_queue.dequeue() == Some(8));
+        assert!(test_queue.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "can not queue onto a StackQueue whose stack is full")]
+    fn stack_queue_with_full_bounded_stack_panics() {
+        let mut test_queue: StackQueue<i32, ArrayStack<i32, 1>> = StackQueue::new();
+
+        test_queue.queue(10);
+        test_queue.queue(42);
+        test_queue.queue(59492957);
+    }
+
+    #[test]
+    fn aggregate_queue_keeps_order() {
+        let mut test_queue = AggregateQueue::new(|a: &String, b: &String| format!("{}{}", a, b));
+
+        assert!(test_queue.aggregate().is_none());
+        for word in ["a", "b", "c"] {
+            test_queue.queue(word.to_string());
+        }
+        assert!(test_queue.aggregate().unwrap() == "abc");
+        assert!(test_queue.dequeue().unwrap() == "a");
+        test_queue.queue("d".to_string());
+        assert!(test_queue.aggregate().unwrap() == "bcd");
+        assert!(test_queue.dequeue().unwrap() == "b");
+        assert!(test_queue.dequeue().unwrap() == "c");
+        test_queue.queue("e".to_string());
+        assert!(test_queue.aggregate().unwrap() == "de");
+        assert!(*test_queue.peek().unwrap() == "d");
+        assert!(test_queue.count() == 2);
+    }
+
+    #[test]
+    fn aggregate_queue_holds_non_clone_data() {
+        struct Sum(i32);
+        let mut test_queue = AggregateQueue::new(|a: &Sum, b: &Sum| Sum(a.0 + b.0));
+
+        for data in [10, 42, 7] {
+            test_queue.queue(Sum(data));
+        }
+        assert!(test_queue.aggregate().unwrap().0 == 59);
+        assert!(test_queue.dequeue().unwrap().0 == 10);
+        assert!(test_queue.aggregate().unwrap().0 == 49);
+        assert!(test_queue.dequeue().unwrap().0 == 42);
+        assert!(test_queue.aggregate().unwrap().0 == 7);
+        assert!(test_queue.dequeue().unwrap().0 == 7);
+        assert!(test_queue.aggregate().is_none());
+    }
+
+    #[test]
+    fn min_and_max_queue_sliding_window() {
+        let test_data = [4, 2, 12, 11, -5, 7, 7, 3, 9, 1];
+        let mut test_min_queue = MinQueue::new();
+        let mut test_max_queue = MaxQueue::new();
+
+        for (index, &data) in test_data.iter().enumerate() {
+            test_min_queue.queue(data);
+            test_max_queue.queue(data);
+            if index >= 3 {
+                test_min_queue.dequeue();
+                test_max_queue.dequeue();
+            }
+            let window = &test_data[index.saturating_sub(2)..=index];
+            assert!(test_min_queue.min() == window.iter().min());
+            assert!(test_max_queue.max() == window.iter().max());
+        }
+        assert!(*test_min_queue.peek().unwrap() == 3);
+        assert!(test_max_queue.count() == 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn blocking_queue_pipeline() {
+        let test_queue = BlockingQueue::new(4);
+        let taken = std::sync::Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            let consumers: Vec<_> = (0..3)
+                .map(|_| {
+                    scope.spawn(|| {
+                        while let Ok(data) = test_queue.take() {
+                            taken.lock().unwrap().push(data);
+                        }
+                    })
+                })
+                .collect();
+            let producers: Vec<_> = (0..4)
+                .map(|producer| {
+                    let test_queue = &test_queue;
+                    scope.spawn(move || {
+                        for data in 0..250 {
+                            assert!(test_queue.put(producer * 250 + data).is_ok());
+                        }
+                    })
+                })
+                .collect();
+            for producer in producers {
+                producer.join().unwrap();
+            }
+            test_queue.close();
+            for consumer in consumers {
+                consumer.join().unwrap();
+            }
+        });
+
+        let mut taken = taken.into_inner().unwrap();
+        taken.sort_unstable();
+        assert!(taken == (0..1000).collect::<Vec<_>>());
+        assert!(test_queue.put(0) == Err(Error::Closed));
+        assert!(test_queue.take() == Err(Error::Closed));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn blocking_queue_timeouts_and_close() {
+        let test_timeout = std::time::Duration::from_millis(10);
+        let test_queue = BlockingQueue::new(2);
+
+        assert!(test_queue.poll(test_timeout) == Err(Error::Timeout));
+        assert!(test_queue.offer(10, test_timeout).is_ok());
+        assert!(test_queue.offer(42, test_timeout).is_ok());
+        assert!(test_queue.offer(59492957, test_timeout) == Err(Error::Timeout));
+        assert!(test_queue.count() == 2 && test_queue.capacity() == 2);
+
+        test_queue.close();
+        assert!(test_queue.is_closed());
+        assert!(test_queue.offer(-2222, test_timeout) == Err(Error::Closed));
+        assert!(test_queue.poll(test_timeout) == Ok(10));
+        assert!(test_queue.take() == Ok(42));
+        assert!(test_queue.poll(test_timeout) == Err(Error::Closed));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn blocking_queue_drain_to_unblocks_producer() {
+        let test_queue = BlockingQueue::new(3);
+        let mut drained = vec![-1];
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for data in 0..6 {
+                    assert!(test_queue.put(data).is_ok());
+                }
+            });
+            while drained.len() < 7 {
+                test_queue.drain_to(&mut drained);
+            }
+        });
+
+        assert!(drained == vec![-1, 0, 1, 2, 3, 4, 5]);
+        assert!(test_queue.drain_to(&mut drained) == 0);
+        assert!(test_queue.is_empty());
+    }
+
+    #[test]
+    fn spsc_queue_single_thread() {
+        let (mut test_producer, mut test_consumer) = spsc_queue(3);
+
+        assert!(test_producer.capacity() == 4);
+        assert!(test_consumer.pop().is_none() && test_consumer.is_empty());
+        for data in 0..4 {
+            assert!(test_producer.push(data).is_ok());
+        }
+        assert!(test_producer.is_full());
+        assert!(test_producer.push(4) == Err(4));
+        assert!(test_consumer.peek() == Some(&0));
+        assert!(test_consumer.pop() == Some(0));
+        assert!(test_producer.push_slice(&[5, 6, 7]) == 1);
+
+        let mut test_target = [0; 3];
+        assert!(test_consumer.pop_slice(&mut test_target) == 3);
+        assert!(test_target == [1, 2, 3]);
+        assert!(test_consumer.pop_slice(&mut test_target) == 1);
+        assert!(test_target[0] == 5);
+        assert!(test_consumer.is_empty());
+    }
+
+    #[test]
+    fn spsc_queue_drops_remaining_data() {
+        let test_data = std::sync::Arc::new(());
+        let (mut test_producer, mut test_consumer) = spsc_queue(4);
+
+        for _ in 0..3 {
+            assert!(test_producer.push(test_data.clone()).is_ok());
+        }
+        drop(test_consumer.pop());
+        drop(test_producer);
+        assert!(std::sync::Arc::strong_count(&test_data) == 3);
+        drop(test_consumer);
+        assert!(std::sync::Arc::strong_count(&test_data) == 1);
+    }
+
+    // Counts its drops and panics when dropped or cloned if told to.
+    struct SpscProbe {
+        drops: std::rc::Rc<std::cell::Cell<usize>>,
+        panic_on_drop: bool,
+        panic_on_clone: bool,
+    }
+
+    impl SpscProbe {
+        fn new(drops: &std::rc::Rc<std::cell::Cell<usize>>) -> Self {
+            SpscProbe {
+                drops: drops.clone(),
+                panic_on_drop: false,
+                panic_on_clone: false,
+            }
+        }
+    }
+
+    impl Clone for SpscProbe {
+        fn clone(&self) -> Self {
+            assert!(!self.panic_on_clone, "probe clone");
+            SpscProbe::new(&self.drops)
+        }
+    }
+
+    impl Drop for SpscProbe {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+            assert!(!self.panic_on_drop, "probe drop");
+        }
+    }
+
+    #[test]
+    fn spsc_queue_pop_slice_survives_panicking_drop() {
+        let test_drops = std::rc::Rc::new(std::cell::Cell::new(0));
+        let (mut test_producer, mut test_consumer) = spsc_queue(4);
+
+        for _ in 0..3 {
+            assert!(test_producer.push(SpscProbe::new(&test_drops)).is_ok());
+        }
+        let mut test_target = [
+            SpscProbe::new(&test_drops),
+            SpscProbe::new(&test_drops),
+            SpscProbe::new(&test_drops),
+        ];
+        test_target[1].panic_on_drop = true;
+        let test_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            test_consumer.pop_slice(&mut test_target)
+        }));
+        assert!(test_result.is_err());
+        assert!(test_drops.get() == 2);
+
+        drop(test_target);
+        assert!(test_drops.get() == 5);
+        assert!(test_consumer.pop().is_some());
+        assert!(test_consumer.pop().is_none());
+        drop(test_producer);
+        drop(test_consumer);
+        assert!(test_drops.get() == 6);
+    }
+
+    #[test]
+    fn spsc_queue_push_slice_survives_panicking_clone() {
+        let test_drops = std::rc::Rc::new(std::cell::Cell::new(0));
+        let (mut test_producer, mut test_consumer) = spsc_queue(4);
+
+        let mut test_data = vec![
+            SpscProbe::new(&test_drops),
+            SpscProbe::new(&test_drops),
+            SpscProbe::new(&test_drops),
+        ];
+        test_data[2].panic_on_clone = true;
+        let test_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            test_producer.push_slice(&test_data)
+        }));
+        assert!(test_result.is_err());
+        assert!(test_consumer.pop().is_some());
+        assert!(test_consumer.pop().is_some());
+        assert!(test_consumer.pop().is_none());
+        assert!(test_drops.get() == 2);
+
+        drop(test_data);
+        drop(test_producer);
+        drop(test_consumer);
+        assert!(test_drops.get() == 5);
+    }
+
+    #[test]
+    fn spsc_queue_stress_keeps_order() {
+        const TEST_LENGTH: usize = 200_000;
+        let (mut test_producer, mut test_consumer) = spsc_queue(64);
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for data in 0..TEST_LENGTH {
+                    let mut data = data;
+                    while let Err(rejected) = test_producer.push(data) {
+                        data = rejected;
+                        std::thread::yield_now();
+                    }
+                }
+            });
+            scope.spawn(move || {
+                let mut expected = 0;
+                while expected < TEST_LENGTH {
+                    match test_consumer.pop() {
+                        Some(data) => {
+                            assert!(data == expected);
+                            expected += 1;
+                        }
+                        None => std::thread::yield_now(),
+                    }
+                }
+                assert!(test_consumer.pop().is_none());
+            });
+        });
+    }
+
+    #[test]
+    fn spsc_queue_stress_slices() {
+        const TEST_LENGTH: usize = 200_000;
+        let (mut test_producer, mut test_consumer) = spsc_queue(256);
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                let test_data: Vec<usize> = (0..TEST_LENGTH).collect();
+                let mut pushed = 0;
+                while pushed < TEST_LENGTH {
+                    let end = (pushed + 37).min(TEST_LENGTH);
+                    match test_producer.push_slice(&test_data[pushed..end]) {
+                        0 => std::thread::yield_now(),
+                        count => pushed += count,
+                    }
+                }
+            });
+            scope.spawn(move || {
+                let mut test_target = [0; 53];
+                let mut expected = 0;
+                while expected < TEST_LENGTH {
+                    let popped = test_consumer.pop_slice(&mut test_target);
+                    if popped == 0 {
+                        std::thread::yield_now();
+                    }
+                    for &data in &test_target[..popped] {
+                        assert!(data == expected);
+                        expected += 1;
+                    }
+                }
+            });
+        });
+    }
+
+    #[test]
+    fn concurrent_queue_single_thread() {
+        let test_queue = ConcurrentQueue::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+
+        assert!(test_queue.is_empty());
+        assert!(test_queue.try_dequeue() == Err(Error::Empty));
+        test_queue.queue(test_data_1);
+        test_queue.queue(test_data_2);
+        test_queue.queue(test_data_3);
+        assert!(test_queue.count() == 3);
+        assert!(test_queue.dequeue().unwrap() == test_data_1);
+        assert!(test_queue.dequeue().unwrap() == test_data_2);
+        assert!(!test_queue.is_empty());
+        assert!(test_queue.dequeue().unwrap() == test_data_3);
+        assert!(test_queue.dequeue().is_none());
+        assert!(test_queue.count() == 0);
+    }
+
+    #[test]
+    fn concurrent_queue_drops_remaining_data() {
+        let test_data = std::sync::Arc::new(());
+        let test_queue = ConcurrentQueue::new();
+
+        for _ in 0..200 {
+            test_queue.queue(test_data.clone());
+        }
+        for _ in 0..100 {
+            drop(test_queue.dequeue());
+        }
+        assert!(std::sync::Arc::strong_count(&test_data) == 101);
+        drop(test_queue);
+        assert!(std::sync::Arc::strong_count(&test_data) == 1);
+    }
+
+    #[test]
+    fn concurrent_queue_stress_keeps_per_producer_order() {
+        const TEST_THREADS: usize = 4;
+        const TEST_ITEMS_PER_THREAD: usize = 20_000;
+        let test_queue = ConcurrentQueue::new();
+
+        let dequeued: Vec<Vec<usize>> = std::thread::scope(|scope| {
+            for thread in 0..TEST_THREADS {
+                let test_queue = &test_queue;
+                scope.spawn(move || {
+                    for item in 0..TEST_ITEMS_PER_THREAD {
+                        test_queue.queue(thread * TEST_ITEMS_PER_THREAD + item);
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..TEST_THREADS)
+                .map(|_| {
+                    let test_queue = &test_queue;
+                    scope.spawn(move || {
+                        let mut dequeued = Vec::new();
+                        for _ in 0..TEST_ITEMS_PER_THREAD {
+                            if let Some(data) = test_queue.dequeue() {
+                                dequeued.push(data);
+                            }
+                        }
+                        dequeued
+                    })
+                })
+                .collect();
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect()
+        });
+
+        // Each consumer must see every producer's items in the order queued.
+        for dequeued in &dequeued {
+            let mut last_seen = [None; TEST_THREADS];
+            for &data in dequeued {
+                let producer = data / TEST_ITEMS_PER_THREAD;
+                assert!(last_seen[producer] < Some(data));
+                last_seen[producer] = Some(data);
+            }
+        }
+        let mut seen = vec![false; TEST_THREADS * TEST_ITEMS_PER_THREAD];
+        for data in dequeued
+            .into_iter()
+            .flatten()
+            .chain(std::iter::from_fn(|| test_queue.dequeue()))
+        {
+            assert!(!seen[data], "{} was dequeued twice", data);
+            seen[data] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen), "data was lost");
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Operation {
+        Queue(usize),
+        Dequeue(Option<usize>),
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct Event {
+        operation: Operation,
+        invoked: usize,
+        returned: usize,
+    }
+
+    // Replays `order` against a sequential model, failing on the first
+    // dequeue whose recorded result the model disagrees with.
+    fn replays_sequentially(history: &[Event], order: &[usize]) -> bool {
+        let mut model = ShuffleQueue::new();
+        order.iter().all(|&index| match history[index].operation {
+            Operation::Queue(data) => {
+                model.queue(data);
+                true
+            }
+            Operation::Dequeue(result) => model.dequeue() == result,
+        })
+    }
+
+    // Searches for an order that respects real time, where an event that
+    // returned before another was invoked must come first, and that the
+    // sequential model accepts.
+    fn is_linearizable(history: &[Event], order: &mut Vec<usize>, done: &mut [bool]) -> bool {
+        if order.len() == history.len() {
+            return true;
+        }
+        let earliest_return = (0..history.len())
+            .filter(|&index| !done[index])
+            .map(|index| history[index].returned)
+            .min()
+            .unwrap();
+        for index in 0..history.len() {
+            if done[index] || history[index].invoked > earliest_return {
+                continue;
+            }
+            order.push(index);
+            done[index] = true;
+            if replays_sequentially(history, order) && is_linearizable(history, order, done) {
+                return true;
+            }
+            done[index] = false;
+            order.pop();
+        }
+        false
+    }
+
+    fn check_history(history: &[Event]) -> bool {
+        is_linearizable(history, &mut Vec::new(), &mut vec![false; history.len()])
+    }
+
+    #[test]
+    fn linearizability_checker_rejects_reordering() {
+        let event = |operation, invoked, returned| Event {
+            operation,
+            invoked,
+            returned,
+        };
+
+        assert!(check_history(&[
+            event(Operation::Queue(1), 0, 3),
+            event(Operation::Queue(2), 1, 2),
+            event(Operation::Dequeue(Some(2)), 4, 5),
+        ]));
+        assert!(!check_history(&[
+            event(Operation::Queue(1), 0, 1),
+            event(Operation::Queue(2), 2, 3),
+            event(Operation::Dequeue(Some(2)), 4, 5),
+        ]));
+        assert!(!check_history(&[
+            event(Operation::Queue(1), 0, 1),
+            event(Operation::Dequeue(None), 2, 3),
+        ]));
+    }
+
+    #[test]
+    fn concurrent_queue_histories_are_linearizable() {
+        const TEST_THREADS: usize = 3;
+        const TEST_OPERATIONS_PER_THREAD: usize = 4;
+
+        for round in 0..300 {
+            let test_queue = ConcurrentQueue::new();
+            let clock = AtomicUsize::new(0);
+            let history: Vec<Event> = std::thread::scope(|scope| {
+                let threads: Vec<_> = (0..TEST_THREADS)
+                    .map(|thread| {
+                        let (test_queue, clock) = (&test_queue, &clock);
+                        scope.spawn(move || {
+                            let mut events = Vec::new();
+                            for step in 0..TEST_OPERATIONS_PER_THREAD {
+                                let invoked = clock.fetch_add(1, Ordering::SeqCst);
+                                let operation = if (round + thread + step) % 2 == 0 {
+                                    let data = thread * TEST_OPERATIONS_PER_THREAD + step;
+                                    test_queue.queue(data);
+                                    Operation::Queue(data)
+                                } else {
+                                    Operation::Dequeue(test_queue.dequeue())
+                                };
+                                let returned = clock.fetch_add(1, Ordering::SeqCst);
+                                events.push(Event {
+                                    operation,
+                                    invoked,
+                                    returned,
+                                });
+                            }
+                            events
+                        })
+                    })
+                    .collect();
+                threads
+                    .into_iter()
+                    .flat_map(|thread| thread.join().unwrap())
+                    .collect()
+            });
+            assert!(check_history(&history), "{:?}", history);
+        }
+    }
+
+    // A single-threaded executor for the async queue tests. Each task's waker
+    // puts the task's index back on the ready list.
+    #[cfg(feature = "std")]
+    struct TestExecutor<'a> {
+        tasks: Vec<Option<core::pin::Pin<Box<dyn core::future::Future<Output = ()> + 'a>>>>,
+        ready: std::sync::Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    #[cfg(feature = "std")]
+    struct TestWaker {
+        task: usize,
+        ready: std::sync::Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    #[cfg(feature = "std")]
+    impl std::task::Wake for TestWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.ready.lock().unwrap().push(self.task);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl<'a> TestExecutor<'a> {
+        fn new() -> Self {
+            TestExecutor {
+                tasks: Vec::new(),
+                ready: std::sync::Arc::new(std::sync::Mutex::new(Vec::new())),
+            }
+        }
+
+        fn spawn(&mut self, future: impl core::future::Future<Output = ()> + 'a) {
+            self.ready.lock().unwrap().push(self.tasks.len());
+            self.tasks.push(Some(Box::pin(future)));
+        }
+
+        // Polls woken tasks until none are ready, returning how many tasks
+        // are still unfinished.
+        fn run(&mut self) -> usize {
+            loop {
+                let Some(task) = self.ready.lock().unwrap().pop() else {
+                    break;
+                };
+                let Some(future) = self.tasks[task].as_mut() else {
+                    continue;
+                };
+                let waker = core::task::Waker::from(std::sync::Arc::new(TestWaker {
+                    task,
+                    ready: self.ready.clone(),
+                }));
+                let mut context = core::task::Context::from_waker(&waker);
+                if future.as_mut().poll(&mut context).is_ready() {
+                    self.tasks[task] = None;
+                }
+            }
+            self.tasks.iter().filter(|task| task.is_some()).count()
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    #[cfg(feature = "std")]
+    impl std::task::Wake for CountingWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn poll_once<F: core::future::Future + Unpin>(
+        future: &mut F,
+        waker: &std::sync::Arc<CountingWaker>,
+    ) -> core::task::Poll<F::Output> {
+        let waker = core::task::Waker::from(waker.clone());
+        core::pin::Pin::new(future).poll(&mut core::task::Context::from_waker(&waker))
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn async_queue_producers_and_consumers() {
+        let test_queue = AsyncQueue::new(2);
+        let popped = std::cell::RefCell::new(Vec::new());
+        let mut test_executor = TestExecutor::new();
+
+        for consumer in 0..2 {
+            let (test_queue, popped) = (&test_queue, &popped);
+            test_executor.spawn(async move {
+                while let Ok(data) = test_queue.pop().await {
+                    popped.borrow_mut().push((consumer, data));
+                }
+            });
+        }
+        for producer in 0..3 {
+            let test_queue = &test_queue;
+            test_executor.spawn(async move {
+                for data in 0..50 {
+                    assert!(test_queue.push(producer * 50 + data).await.is_ok());
+                }
+            });
+        }
+        assert!(test_executor.run() == 2);
+        assert!(test_queue.count() == 0);
+
+        test_queue.close();
+        assert!(test_executor.run() == 0);
+        drop(test_executor);
+        let mut popped: Vec<_> = popped
+            .into_inner()
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect();
+        for producer in 0..3 {
+            let from_producer: Vec<_> = popped
+                .iter()
+                .copied()
+                .filter(|data| data / 50 == producer)
+                .collect();
+            assert!(from_producer == (producer * 50..producer * 50 + 50).collect::<Vec<_>>());
+        }
+        popped.sort_unstable();
+        assert!(popped == (0..150).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn async_queue_backpressure() {
+        let test_queue = AsyncQueue::new(1);
+        let test_waker = std::sync::Arc::new(CountingWaker::default());
+
+        assert!(poll_once(&mut test_queue.push(10), &test_waker).is_ready());
+        let mut test_push = test_queue.push(42);
+        assert!(poll_once(&mut test_push, &test_waker).is_pending());
+        assert!(poll_once(&mut test_push, &test_waker).is_pending());
+        assert!(test_waker.0.load(Ordering::SeqCst) == 0);
+
+        assert!(poll_once(&mut test_queue.pop(), &test_waker) == core::task::Poll::Ready(Ok(10)));
+        assert!(test_waker.0.load(Ordering::SeqCst) == 1);
+        assert!(poll_once(&mut test_push, &test_waker) == core::task::Poll::Ready(Ok(())));
+        assert!(test_queue.count() == 1 && test_queue.capacity() == 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn async_queue_close_wakes_waiters() {
+        let test_queue = AsyncQueue::new(1);
+        let test_waker = std::sync::Arc::new(CountingWaker::default());
+        let mut test_pop = test_queue.pop();
+
+        assert!(poll_once(&mut test_pop, &test_waker).is_pending());
+        test_queue.close();
+        assert!(test_queue.is_closed());
+        assert!(test_waker.0.load(Ordering::SeqCst) == 1);
+        assert!(
+            poll_once(&mut test_pop, &test_waker) == core::task::Poll::Ready(Err(Error::Closed))
+        );
+        assert!(
+            poll_once(&mut test_queue.push(10), &test_waker)
+                == core::task::Poll::Ready(Err(Error::Closed))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn async_queue_dropped_waiter_passes_wakeup_on() {
+        let test_queue = AsyncQueue::new(1);
+        let first_waker = std::sync::Arc::new(CountingWaker::default());
+        let second_waker = std::sync::Arc::new(CountingWaker::default());
+        let mut first_pop = test_queue.pop();
+        let mut second_pop = test_queue.pop();
+
+        assert!(poll_once(&mut first_pop, &first_waker).is_pending());
+        assert!(poll_once(&mut second_pop, &second_waker).is_pending());
+        assert!(poll_once(&mut test_queue.push(10), &first_waker).is_ready());
+        assert!(first_waker.0.load(Ordering::SeqCst) == 1);
+        assert!(second_waker.0.load(Ordering::SeqCst) == 0);
+
+        drop(first_pop);
+        assert!(second_waker.0.load(Ordering::SeqCst) == 1);
+        assert!(poll_once(&mut second_pop, &second_waker) == core::task::Poll::Ready(Ok(10)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn async_queue_skips_cancelled_waiters() {
+        let test_queue = AsyncQueue::new(1);
+        let test_wakers: Vec<_> = (0..40)
+            .map(|_| std::sync::Arc::new(CountingWaker::default()))
+            .collect();
+        let mut test_pops: Vec<_> = (0..40).map(|_| Some(test_queue.pop())).collect();
+
+        for (test_pop, test_waker) in test_pops.iter_mut().zip(&test_wakers) {
+            assert!(poll_once(test_pop.as_mut().unwrap(), test_waker).is_pending());
+        }
+        for test_pop in test_pops.iter_mut().step_by(2) {
+            drop(test_pop.take());
+        }
+        {
+            let state = test_queue.state.lock().unwrap();
+            assert!(state.poppers.queued == 20);
+            assert!(state.poppers.order.count() <= 2 * 20 + 8);
+        }
+        assert!(test_wakers
+            .iter()
+            .all(|waker| waker.0.load(Ordering::SeqCst) == 0));
+
+        assert!(poll_once(&mut test_queue.push(10), &test_wakers[0]).is_ready());
+        let woken: Vec<usize> = (0..40)
+            .filter(|&index| test_wakers[index].0.load(Ordering::SeqCst) == 1)
+            .collect();
+        assert!(woken == [1]);
+        assert!(
+            poll_once(test_pops[1].as_mut().unwrap(), &test_wakers[1])
+                == core::task::Poll::Ready(Ok(10))
+        );
     }
 }
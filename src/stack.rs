@@ -1,138 +1,1020 @@
-trait Stack<T: Clone> {
-    fn push(&mut self, data: T);
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::Rev;
+use core::marker::PhantomData;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 
-    fn pop(&mut self) -> Result<T, Error>;
+use crate::error::Error;
+use crate::hazard::HazardDomain;
+use crate::queue::{Deque, Queue, RingQueue};
 
-    fn peek(&self) -> Result<T, Error>;
+pub trait Stack<T> {
+    /// Fails with `Error::CapacityExceeded` if a bounded stack is full.
+    fn push(&mut self, data: T) -> Result<(), Error>;
+
+    fn pop(&mut self) -> Option<T>;
+
+    fn peek(&self) -> Option<&T>;
+
+    fn peek_mut(&mut self) -> Option<&mut T>;
 
     fn count(&self) -> usize;
 
-    fn is_empty(&self) -> bool;
+    fn is_empty(&self) -> bool;
+
+    fn try_pop(&mut self) -> Result<T, Error> {
+        self.pop().ok_or(Error::Empty)
+    }
+
+    fn try_peek(&self) -> Result<&T, Error> {
+        self.peek().ok_or(Error::Empty)
+    }
+}
+
+/// A stack without a depth limit, so pushing onto it can not fail.
+pub trait UnboundedStack<T>: Stack<T> {
+    fn push_unbounded(&mut self, data: T);
+}
+
+pub struct VecStack<T> {
+    stack: Vec<T>,
+    count: usize,
+}
+
+pub struct VecStackIter<'a, T>(Rev<core::slice::Iter<'a, T>>);
+
+pub struct VecStackIterMut<'a, T>(Rev<core::slice::IterMut<'a, T>>);
+
+pub struct VecStackIntoIter<T>(Rev<alloc::vec::IntoIter<T>>);
+
+pub struct VecStackDrain<'a, T>(Rev<alloc::vec::Drain<'a, T>>);
+
+impl<T> VecStack<T> {
+    pub fn new() -> Self {
+        VecStack {
+            stack: Vec::<T>::new(),
+            count: 0,
+        }
+    }
+
+    pub fn iter(&self) -> VecStackIter<'_, T> {
+        VecStackIter(self.stack.iter().rev())
+    }
+
+    pub fn iter_mut(&mut self) -> VecStackIterMut<'_, T> {
+        VecStackIterMut(self.stack.iter_mut().rev())
+    }
+
+    pub fn drain(&mut self) -> VecStackDrain<'_, T> {
+        self.count = 0;
+        VecStackDrain(self.stack.drain(..).rev())
+    }
+}
+
+impl<T> Default for VecStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Stack<T> for VecStack<T> {
+    fn push(&mut self, data: T) -> Result<(), Error> {
+        self.push_unbounded(data);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.count == 0 {
+            None
+        } else {
+            self.count -= 1;
+            Some(self.stack.remove(self.count))
+        }
+    }
+
+    fn peek(&self) -> Option<&T> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(&self.stack[self.count - 1])
+        }
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(&mut self.stack[self.count - 1])
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+impl<T> UnboundedStack<T> for VecStack<T> {
+    fn push_unbounded(&mut self, data: T) {
+        self.stack.insert(self.count, data);
+        self.count += 1;
+    }
+}
+
+impl<'a, T> Iterator for VecStackIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T> Iterator for VecStackIterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> Iterator for VecStackIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> Iterator for VecStackDrain<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> IntoIterator for VecStack<T> {
+    type Item = T;
+    type IntoIter = VecStackIntoIter<T>;
+
+    fn into_iter(self) -> VecStackIntoIter<T> {
+        VecStackIntoIter(self.stack.into_iter().rev())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a VecStack<T> {
+    type Item = &'a T;
+    type IntoIter = VecStackIter<'a, T>;
+
+    fn into_iter(self) -> VecStackIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut VecStack<T> {
+    type Item = &'a mut T;
+    type IntoIter = VecStackIterMut<'a, T>;
+
+    fn into_iter(self) -> VecStackIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for VecStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = VecStack::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+impl<T> Extend<T> for VecStack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.push_unbounded(data);
+        }
+    }
+}
+
+pub struct ListStack<T> {
+    root_node: Option<Box<ListStackNode<T>>>,
+    count: usize,
+}
+
+struct ListStackNode<T> {
+    data: T,
+    next_node: Option<Box<ListStackNode<T>>>,
+}
+
+pub struct ListStackIter<'a, T> {
+    next_node: Option<&'a ListStackNode<T>>,
+    remaining: usize,
+}
+
+pub struct ListStackIterMut<'a, T> {
+    next_node: Option<&'a mut ListStackNode<T>>,
+    remaining: usize,
+}
+
+pub struct ListStackIntoIter<T>(ListStack<T>);
+
+pub struct ListStackDrain<'a, T>(&'a mut ListStack<T>);
+
+impl<T> ListStack<T> {
+    pub fn new() -> Self {
+        ListStack {
+            root_node: None,
+            count: 0,
+        }
+    }
+
+    pub fn iter(&self) -> ListStackIter<'_, T> {
+        ListStackIter {
+            next_node: self.root_node.as_deref(),
+            remaining: self.count,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> ListStackIterMut<'_, T> {
+        ListStackIterMut {
+            next_node: self.root_node.as_deref_mut(),
+            remaining: self.count,
+        }
+    }
+
+    pub fn drain(&mut self) -> ListStackDrain<'_, T> {
+        ListStackDrain(self)
+    }
+}
+
+impl<T> Default for ListStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ListStackNode<T> {
+    fn new(data: T, next_node: Option<Box<ListStackNode<T>>>) -> Self {
+        ListStackNode { data, next_node }
+    }
+}
+
+impl<T> Stack<T> for ListStack<T> {
+    fn push(&mut self, data: T) -> Result<(), Error> {
+        self.push_unbounded(data);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.count == 0 {
+            None
+        } else {
+            let mut old_root_node = self.root_node.take();
+            let new_node = old_root_node.as_mut().unwrap().next_node.take();
+            self.root_node = new_node;
+            self.count -= 1;
+            Some(old_root_node.unwrap().data)
+        }
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.root_node.as_ref().map(|node| &node.data)
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.root_node.as_mut().map(|node| &mut node.data)
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+impl<T> UnboundedStack<T> for ListStack<T> {
+    fn push_unbounded(&mut self, data: T) {
+        let new_node = ListStackNode::new(data, self.root_node.take());
+        self.root_node = Some(Box::new(new_node));
+        self.count += 1;
+    }
+}
+
+impl<T> Drop for ListStack<T> {
+    fn drop(&mut self) {
+        let mut current_node = self.root_node.take();
+        while let Some(mut node) = current_node {
+            current_node = node.next_node.take();
+        }
+    }
+}
+
+impl<T: Clone> Clone for ListStack<T> {
+    fn clone(&self) -> Self {
+        let mut new_stack = ListStack::new();
+        let mut tail_node = &mut new_stack.root_node;
+        for data in self.iter() {
+            let new_node = tail_node.insert(Box::new(ListStackNode::new(data.clone(), None)));
+            tail_node = &mut new_node.next_node;
+        }
+        new_stack.count = self.count;
+        new_stack
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ListStack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T> Iterator for ListStackIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.next_node.map(|node| {
+            self.next_node = node.next_node.as_deref();
+            self.remaining -= 1;
+            &node.data
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> Iterator for ListStackIterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.next_node.take().map(|node| {
+            self.next_node = node.next_node.as_deref_mut();
+            self.remaining -= 1;
+            &mut node.data
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> Iterator for ListStackIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.count, Some(self.0.count))
+    }
+}
+
+impl<T> Iterator for ListStackDrain<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.count, Some(self.0.count))
+    }
+}
+
+impl<T> Drop for ListStackDrain<'_, T> {
+    fn drop(&mut self) {
+        while self.0.pop().is_some() {}
+    }
+}
+
+impl<T> IntoIterator for ListStack<T> {
+    type Item = T;
+    type IntoIter = ListStackIntoIter<T>;
+
+    fn into_iter(self) -> ListStackIntoIter<T> {
+        ListStackIntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ListStack<T> {
+    type Item = &'a T;
+    type IntoIter = ListStackIter<'a, T>;
+
+    fn into_iter(self) -> ListStackIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ListStack<T> {
+    type Item = &'a mut T;
+    type IntoIter = ListStackIterMut<'a, T>;
+
+    fn into_iter(self) -> ListStackIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for ListStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = ListStack::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+impl<T> Extend<T> for ListStack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.push_unbounded(data);
+        }
+    }
+}
+
+pub struct ArrayStack<T, const N: usize> {
+    stack: [MaybeUninit<T>; N],
+    count: usize,
+}
+
+impl<T, const N: usize> ArrayStack<T, N> {
+    pub const fn new() -> Self {
+        ArrayStack {
+            stack: [const { MaybeUninit::uninit() }; N],
+            count: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count == N
+    }
+}
+
+impl<T, const N: usize> Default for ArrayStack<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayStack<T, N> {
+    fn drop(&mut self) {
+        for slot in &mut self.stack[..self.count] {
+            // SAFETY: every slot below `count` holds an initialised value.
+            unsafe { slot.assume_init_drop() };
+        }
+    }
+}
+
+impl<T, const N: usize> Stack<T> for ArrayStack<T, N> {
+    fn push(&mut self, data: T) -> Result<(), Error> {
+        if self.count == N {
+            Err(Error::CapacityExceeded)
+        } else {
+            self.stack[self.count].write(data);
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.count == 0 {
+            None
+        } else {
+            self.count -= 1;
+            // SAFETY: the slot at the old top was initialised and is no longer counted.
+            Some(unsafe { self.stack[self.count].assume_init_read() })
+        }
+    }
+
+    fn peek(&self) -> Option<&T> {
+        if self.count == 0 {
+            None
+        } else {
+            // SAFETY: every slot below `count` holds an initialised value.
+            Some(unsafe { self.stack[self.count - 1].assume_init_ref() })
+        }
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        if self.count == 0 {
+            None
+        } else {
+            // SAFETY: every slot below `count` holds an initialised value.
+            Some(unsafe { self.stack[self.count - 1].assume_init_mut() })
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+pub struct AggregateStack<T, F> {
+    stack: VecStack<(T, T)>,
+    combine: F,
+}
+
+impl<T: Clone, F: Fn(&T, &T) -> T> AggregateStack<T, F> {
+    pub fn new(combine: F) -> Self {
+        AggregateStack {
+            stack: VecStack::new(),
+            combine,
+        }
+    }
+
+    pub fn push(&mut self, data: T) {
+        let aggregate = match self.stack.peek() {
+            Some((_, aggregate_below)) => (self.combine)(aggregate_below, &data),
+            None => data.clone(),
+        };
+        self.stack.push_unbounded((data, aggregate));
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.stack.pop().map(|(data, _)| data)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.stack.peek().map(|(data, _)| data)
+    }
+
+    pub fn aggregate(&self) -> Option<&T> {
+        self.stack.peek().map(|(_, aggregate)| aggregate)
+    }
+
+    pub fn count(&self) -> usize {
+        self.stack.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+}
+
+pub struct MinStack<T>(AggregateStack<T, fn(&T, &T) -> T>);
+
+impl<T: Ord + Clone> MinStack<T> {
+    pub fn new() -> Self {
+        MinStack(AggregateStack::new(|a, b| a.min(b).clone()))
+    }
+
+    pub fn push(&mut self, data: T) {
+        self.0.push(data);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.0.peek()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.0.aggregate()
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: Ord + Clone> Default for MinStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct MaxStack<T>(AggregateStack<T, fn(&T, &T) -> T>);
+
+impl<T: Ord + Clone> MaxStack<T> {
+    pub fn new() -> Self {
+        MaxStack(AggregateStack::new(|a, b| a.max(b).clone()))
+    }
+
+    pub fn push(&mut self, data: T) {
+        self.0.push(data);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.0.peek()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.0.aggregate()
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: Ord + Clone> Default for MaxStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct PersistentStack<T> {
+    root_node: Option<Rc<PersistentStackNode<T>>>,
+    count: usize,
+}
+
+struct PersistentStackNode<T> {
+    data: T,
+    next_node: Option<Rc<PersistentStackNode<T>>>,
+}
+
+pub struct PersistentStackIter<'a, T> {
+    next_node: Option<&'a PersistentStackNode<T>>,
+    remaining: usize,
+}
+
+impl<T> PersistentStack<T> {
+    pub fn new() -> Self {
+        PersistentStack {
+            root_node: None,
+            count: 0,
+        }
+    }
+
+    /// Returns a new version with `data` on top, sharing every node of `self`.
+    pub fn push(&self, data: T) -> Self {
+        PersistentStack {
+            root_node: Some(Rc::new(PersistentStackNode {
+                data,
+                next_node: self.root_node.clone(),
+            })),
+            count: self.count + 1,
+        }
+    }
+
+    /// Returns a new version without the top element, or `None` if `self` is empty.
+    pub fn pop(&self) -> Option<Self> {
+        self.root_node.as_ref().map(|node| PersistentStack {
+            root_node: node.next_node.clone(),
+            count: self.count - 1,
+        })
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root_node.as_ref().map(|node| &node.data)
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn iter(&self) -> PersistentStackIter<'_, T> {
+        PersistentStackIter {
+            next_node: self.root_node.as_deref(),
+            remaining: self.count,
+        }
+    }
+}
+
+impl<T> Default for PersistentStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for PersistentStack<T> {
+    fn clone(&self) -> Self {
+        PersistentStack {
+            root_node: self.root_node.clone(),
+            count: self.count,
+        }
+    }
+}
+
+impl<T> Drop for PersistentStack<T> {
+    fn drop(&mut self) {
+        let mut current_node = self.root_node.take();
+        while let Some(node) = current_node {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => current_node = node.next_node.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentStack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<T> for PersistentStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentStack::new(), |stack, data| stack.push(data))
+    }
+}
+
+impl<'a, T> Iterator for PersistentStackIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.next_node.map(|node| {
+            self.next_node = node.next_node.as_deref();
+            self.remaining -= 1;
+            &node.data
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PersistentStack<T> {
+    type Item = &'a T;
+    type IntoIter = PersistentStackIter<'a, T>;
+
+    fn into_iter(self) -> PersistentStackIter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct ConcurrentStack<T> {
+    root_node: AtomicPtr<ConcurrentStackNode<T>>,
+    count: AtomicUsize,
+    hazards: HazardDomain,
+    _marker: PhantomData<T>,
+}
+
+struct ConcurrentStackNode<T> {
+    data: ManuallyDrop<T>,
+    next_node: *mut ConcurrentStackNode<T>,
+}
+
+unsafe impl<T: Send> Sync for ConcurrentStack<T> {}
+
+impl<T> ConcurrentStack<T> {
+    pub const fn new() -> Self {
+        ConcurrentStack {
+            root_node: AtomicPtr::new(ptr::null_mut()),
+            count: AtomicUsize::new(0),
+            hazards: HazardDomain::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn push(&self, data: T) {
+        let new_node = Box::into_raw(Box::new(ConcurrentStackNode {
+            data: ManuallyDrop::new(data),
+            next_node: ptr::null_mut(),
+        }));
+        self.count.fetch_add(1, Ordering::Relaxed);
+        loop {
+            let root_node = self.root_node.load(Ordering::Relaxed);
+            // SAFETY: the new node is not shared until the exchange succeeds.
+            unsafe { (*new_node).next_node = root_node };
+            if self
+                .root_node
+                .compare_exchange_weak(root_node, new_node, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let hazard = self.hazards.acquire();
+        loop {
+            let root_node = hazard.protect(&self.root_node);
+            if root_node.is_null() {
+                return None;
+            }
+            // SAFETY: the hazard keeps the protected node from being freed,
+            // and a node's link never changes once it has been pushed.
+            let next_node = unsafe { (*root_node).next_node };
+            if self
+                .root_node
+                .compare_exchange(root_node, next_node, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                hazard.clear();
+                self.count.fetch_sub(1, Ordering::Relaxed);
+                // SAFETY: winning the exchange unlinked the node, so this
+                // thread alone takes its data and retires it.
+                unsafe {
+                    let data = ptr::read(&(*root_node).data);
+                    self.hazards.retire(root_node);
+                    return Some(ManuallyDrop::into_inner(data));
+                }
+            }
+        }
+    }
+
+    /// A snapshot that may already be stale while other threads push or pop.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root_node.load(Ordering::Acquire).is_null()
+    }
 }
 
-#[derive(Debug)]
-pub struct Error {
-    details: String,
+impl<T> Default for ConcurrentStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Error {
-    pub fn new(msg: &str) -> Error {
-        Error {
-            details: msg.to_string(),
+impl<T> Drop for ConcurrentStack<T> {
+    fn drop(&mut self) {
+        let mut current_node = *self.root_node.get_mut();
+        while !current_node.is_null() {
+            // SAFETY: with `&mut self` every remaining node is owned by the stack.
+            let mut node = unsafe { Box::from_raw(current_node) };
+            unsafe { ManuallyDrop::drop(&mut node.data) };
+            current_node = node.next_node;
         }
     }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.details)
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    Reject,
+    DropOldest,
+    /// Waits for room in `SyncBoundedStack`. A single-threaded `BoundedStack`
+    /// has no one to wait for, so it rejects like `Reject`.
+    Block,
 }
 
-pub struct VecStack<T> {
-    stack: Vec<T>,
-    count: usize,
+/// A stack with a maximum depth. The elements sit in a ring, bottom first, so
+/// `OverflowPolicy::DropOldest` evicts the bottom in O(1).
+pub struct BoundedStack<T> {
+    stack: RingQueue<T>,
+    max_depth: usize,
+    policy: OverflowPolicy,
+    overflow_count: usize,
 }
 
-impl<T> VecStack<T> {
-    pub fn new() -> Self {
-        VecStack {
-            stack: Vec::<T>::new(),
-            count: 0,
+impl<T> BoundedStack<T> {
+    pub fn new(max_depth: usize, policy: OverflowPolicy) -> Self {
+        assert!(
+            max_depth > 0,
+            "BoundedStack needs a max depth of at least 1"
+        );
+        BoundedStack {
+            stack: RingQueue::new(),
+            max_depth,
+            policy,
+            overflow_count: 0,
         }
     }
-}
 
-impl<T: Clone> Stack<T> for VecStack<T> {
-    fn push(&mut self, data: T) {
-        self.stack.insert(self.count, data);
-        self.count += 1;
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
     }
 
-    fn pop(&mut self) -> Result<T, Error> {
-        if self.count == 0 {
-            Err(Error::new("can not pop empty stack"))
-        } else {
-            self.count -= 1;
-            Ok(self.stack.remove(self.count))
-        }
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
     }
 
-    fn peek(&self) -> Result<T, Error> {
-        if self.count == 0 {
-            Err(Error::new("stack is empty"))
-        } else {
-            Ok(self.stack[self.count - 1].clone())
+    pub fn is_full(&self) -> bool {
+        self.stack.count() == self.max_depth
+    }
+
+    /// How many pushes found the stack full, whether they were rejected or
+    /// evicted the oldest element.
+    pub fn overflow_count(&self) -> usize {
+        self.overflow_count
+    }
+}
+
+impl<T> Stack<T> for BoundedStack<T> {
+    fn push(&mut self, data: T) -> Result<(), Error> {
+        if self.is_full() {
+            self.overflow_count += 1;
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    self.stack.pop_front();
+                }
+                OverflowPolicy::Reject | OverflowPolicy::Block => {
+                    return Err(Error::CapacityExceeded)
+                }
+            }
         }
+        self.stack.push_back(data);
+        Ok(())
     }
 
-    fn count(&self) -> usize {
-        self.count
+    fn pop(&mut self) -> Option<T> {
+        self.stack.pop_back()
     }
 
-    fn is_empty(&self) -> bool {
-        self.count == 0
+    fn peek(&self) -> Option<&T> {
+        self.stack.back()
     }
-}
 
-pub struct ListStack<T> {
-    root_node: Option<Box<ListStackNode<T>>>,
-    count: usize,
-}
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        let top = self.stack.count().checked_sub(1)?;
+        self.stack.get_mut(top)
+    }
 
-struct ListStackNode<T> {
-    data: T,
-    next_node: Option<Box<ListStackNode<T>>>,
-}
+    fn count(&self) -> usize {
+        self.stack.count()
+    }
 
-impl<T> ListStack<T> {
-    pub fn new() -> Self {
-        ListStack {
-            root_node: None,
-            count: 0,
-        }
+    fn is_empty(&self) -> bool {
+        self.stack.is_empty()
     }
 }
 
-impl<T> ListStackNode<T> {
-    fn new(data: T, next_node: Option<Box<ListStackNode<T>>>) -> Self {
-        ListStackNode { data, next_node }
-    }
+#[cfg(feature = "std")]
+pub struct SyncBoundedStack<T> {
+    stack: std::sync::Mutex<BoundedStack<T>>,
+    not_full: std::sync::Condvar,
 }
 
-impl<T: Clone> Stack<T> for ListStack<T> {
-    fn push(&mut self, data: T) {
-        let new_node = ListStackNode::new(data, self.root_node.take());
-        self.root_node = Some(Box::new(new_node));
-        self.count += 1;
+#[cfg(feature = "std")]
+impl<T> SyncBoundedStack<T> {
+    pub fn new(max_depth: usize, policy: OverflowPolicy) -> Self {
+        SyncBoundedStack {
+            stack: std::sync::Mutex::new(BoundedStack::new(max_depth, policy)),
+            not_full: std::sync::Condvar::new(),
+        }
     }
 
-    fn pop(&mut self) -> Result<T, Error> {
-        if self.count == 0 {
-            Err(Error::new("can not pop empty stack"))
-        } else {
-            let mut old_root_node = self.root_node.take();
-            let new_node = old_root_node.as_mut().unwrap().next_node.take();
-            self.root_node = new_node;
-            self.count -= 1;
-            Ok(old_root_node.unwrap().data)
+    /// Under `OverflowPolicy::Block` this waits until another thread pops.
+    pub fn push(&self, data: T) -> Result<(), Error> {
+        let mut stack = self.stack.lock().unwrap();
+        if stack.policy == OverflowPolicy::Block && stack.is_full() {
+            stack.overflow_count += 1;
+            stack = self
+                .not_full
+                .wait_while(stack, |stack| stack.is_full())
+                .unwrap();
         }
+        stack.push(data)
     }
 
-    fn peek(&self) -> Result<T, Error> {
-        if self.count == 0 {
-            Err(Error::new("stack is empty"))
-        } else {
-            Ok(self.root_node.as_ref().unwrap().data.clone())
+    pub fn pop(&self) -> Option<T> {
+        let data = self.stack.lock().unwrap().pop();
+        if data.is_some() {
+            self.not_full.notify_one();
         }
+        data
     }
 
-    fn count(&self) -> usize {
-        self.count
+    pub fn count(&self) -> usize {
+        self.stack.lock().unwrap().count()
     }
 
-    fn is_empty(&self) -> bool {
-        self.count == 0
+    pub fn is_empty(&self) -> bool {
+        self.stack.lock().unwrap().is_empty()
+    }
+
+    pub fn overflow_count(&self) -> usize {
+        self.stack.lock().unwrap().overflow_count()
     }
 }
 
@@ -146,11 +1028,11 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
         assert!(test_stack.count() == 0);
-        test_stack.push(test_data_1.clone());
+        test_stack.push_unbounded(test_data_1);
         assert!(test_stack.count() == 1);
-        test_stack.push(test_data_2.clone());
+        test_stack.push_unbounded(test_data_2);
         assert!(test_stack.count() == 2);
-        test_stack.push(test_data_3.clone());
+        test_stack.push_unbounded(test_data_3);
         assert!(test_stack.count() == 3);
     }
 
@@ -159,11 +1041,11 @@ mod tests {
         let mut test_stack = VecStack::new();
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        test_stack.push(test_data_1.clone());
-        test_stack.push(test_data_2.clone());
-        test_stack.push(test_data_3.clone());
+        assert!(test_stack.push(test_data_1).is_ok());
+        assert!(test_stack.push(test_data_2).is_ok());
+        assert!(test_stack.push(test_data_3).is_ok());
         assert!(test_stack.count() == 3);
-        assert!(test_stack.is_empty() == false);
+        assert!(!test_stack.is_empty());
     }
 
     #[test]
@@ -171,9 +1053,9 @@ mod tests {
         let mut test_stack = VecStack::new();
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        test_stack.push(test_data_1.clone());
-        test_stack.push(test_data_2.clone());
-        test_stack.push(test_data_3.clone());
+        test_stack.push_unbounded(test_data_1);
+        test_stack.push_unbounded(test_data_2);
+        test_stack.push_unbounded(test_data_3);
 
         assert!(test_stack.pop().unwrap() == test_data_3);
         assert!(test_stack.pop().unwrap() == test_data_2);
@@ -181,7 +1063,7 @@ mod tests {
 
         assert!(test_stack.count() == 0);
         assert!(test_stack.is_empty());
-        assert!(test_stack.pop().is_err());
+        assert!(test_stack.pop().is_none());
     }
 
     #[test]
@@ -189,18 +1071,18 @@ mod tests {
         let mut test_stack = VecStack::new();
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        test_stack.push(test_data_1.clone());
-        test_stack.push(test_data_2.clone());
-        test_stack.push(test_data_3.clone());
+        test_stack.push_unbounded(test_data_1);
+        test_stack.push_unbounded(test_data_2);
+        test_stack.push_unbounded(test_data_3);
 
-        assert!(test_stack.peek().unwrap() == test_data_3);
+        assert!(*test_stack.peek().unwrap() == test_data_3);
         assert!(test_stack.pop().unwrap() == test_data_3);
-        assert!(test_stack.peek().unwrap() == test_data_2);
+        assert!(*test_stack.peek().unwrap() == test_data_2);
         assert!(test_stack.pop().unwrap() == test_data_2);
-        assert!(test_stack.peek().unwrap() == test_data_1);
+        assert!(*test_stack.peek().unwrap() == test_data_1);
         assert!(test_stack.pop().unwrap() == test_data_1);
         assert!(test_stack.is_empty());
-        assert!(test_stack.peek().is_err());
+        assert!(test_stack.peek().is_none());
     }
 
     #[test]
@@ -209,11 +1091,11 @@ mod tests {
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
         assert!(test_stack.count() == 0);
-        test_stack.push(test_data_1.clone());
+        test_stack.push_unbounded(test_data_1);
         assert!(test_stack.count() == 1);
-        test_stack.push(test_data_2.clone());
+        test_stack.push_unbounded(test_data_2);
         assert!(test_stack.count() == 2);
-        test_stack.push(test_data_3.clone());
+        test_stack.push_unbounded(test_data_3);
         assert!(test_stack.count() == 3);
     }
 
@@ -222,11 +1104,11 @@ mod tests {
         let mut test_stack = ListStack::new();
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        test_stack.push(test_data_1.clone());
-        test_stack.push(test_data_2.clone());
-        test_stack.push(test_data_3.clone());
+        assert!(test_stack.push(test_data_1).is_ok());
+        assert!(test_stack.push(test_data_2).is_ok());
+        assert!(test_stack.push(test_data_3).is_ok());
         assert!(test_stack.count() == 3);
-        assert!(test_stack.is_empty() == false);
+        assert!(!test_stack.is_empty());
     }
 
     #[test]
@@ -234,9 +1116,9 @@ mod tests {
         let mut test_stack = ListStack::new();
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        test_stack.push(test_data_1.clone());
-        test_stack.push(test_data_2.clone());
-        test_stack.push(test_data_3.clone());
+        test_stack.push_unbounded(test_data_1);
+        test_stack.push_unbounded(test_data_2);
+        test_stack.push_unbounded(test_data_3);
 
         assert!(test_stack.pop().unwrap() == test_data_3);
         assert!(test_stack.pop().unwrap() == test_data_2);
@@ -244,7 +1126,7 @@ mod tests {
 
         assert!(test_stack.count() == 0);
         assert!(test_stack.is_empty());
-        assert!(test_stack.pop().is_err());
+        assert!(test_stack.pop().is_none());
     }
 
     #[test]
@@ -252,17 +1134,563 @@ mod tests {
         let mut test_stack = ListStack::new();
         let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        test_stack.push(test_data_1.clone());
-        test_stack.push(test_data_2.clone());
-        test_stack.push(test_data_3.clone());
+        test_stack.push_unbounded(test_data_1);
+        test_stack.push_unbounded(test_data_2);
+        test_stack.push_unbounded(test_data_3);
+
+        assert!(*test_stack.peek().unwrap() == test_data_3);
+        assert!(test_stack.pop().unwrap() == test_data_3);
+        assert!(*test_stack.peek().unwrap() == test_data_2);
+        assert!(test_stack.pop().unwrap() == test_data_2);
+        assert!(*test_stack.peek().unwrap() == test_data_1);
+        assert!(test_stack.pop().unwrap() == test_data_1);
+        assert!(test_stack.is_empty());
+        assert!(test_stack.peek().is_none());
+    }
+
+    #[test]
+    fn vec_stack_peek_mut() {
+        let mut test_stack = VecStack::new();
+        let (test_data_1, test_data_2) = (10, 42);
+
+        assert!(test_stack.peek_mut().is_none());
+        test_stack.push_unbounded(test_data_1);
+        *test_stack.peek_mut().unwrap() = test_data_2;
+        assert!(*test_stack.peek().unwrap() == test_data_2);
+        assert!(test_stack.count() == 1);
+    }
+
+    #[test]
+    fn list_stack_peek_mut() {
+        let mut test_stack = ListStack::new();
+        let (test_data_1, test_data_2) = (10, 42);
+
+        assert!(test_stack.peek_mut().is_none());
+        test_stack.push_unbounded(test_data_1);
+        *test_stack.peek_mut().unwrap() = test_data_2;
+        assert!(*test_stack.peek().unwrap() == test_data_2);
+        assert!(test_stack.count() == 1);
+    }
+
+    #[test]
+    fn stack_try_pop_and_try_peek() {
+        let mut test_vec_stack = VecStack::new();
+        let mut test_list_stack = ListStack::new();
+
+        assert!(test_vec_stack.try_pop() == Err(Error::Empty));
+        assert!(test_vec_stack.try_peek() == Err(Error::Empty));
+        assert!(test_list_stack.try_pop() == Err(Error::Empty));
+        assert!(test_list_stack.try_peek() == Err(Error::Empty));
+
+        test_vec_stack.push_unbounded(10);
+        test_list_stack.push_unbounded(10);
+        assert!(test_vec_stack.try_peek() == Ok(&10));
+        assert!(test_vec_stack.try_pop() == Ok(10));
+        assert!(test_list_stack.try_peek() == Ok(&10));
+        assert!(test_list_stack.try_pop() == Ok(10));
+    }
+
+    #[test]
+    fn stack_holds_non_clone_data() {
+        let mut test_vec_stack: VecStack<Box<dyn Fn(i32) -> i32>> = VecStack::new();
+        let mut test_list_stack: ListStack<Box<dyn Fn(i32) -> i32>> = ListStack::new();
+
+        test_vec_stack.push_unbounded(Box::new(|x| x + 1));
+        test_vec_stack.push_unbounded(Box::new(|x| x * 2));
+        test_list_stack.push_unbounded(Box::new(|x| x + 1));
+        test_list_stack.push_unbounded(Box::new(|x| x * 2));
+
+        assert!(test_vec_stack.peek().unwrap()(10) == 20);
+        assert!(test_vec_stack.pop().unwrap()(10) == 20);
+        assert!(test_vec_stack.pop().unwrap()(10) == 11);
+        assert!(test_list_stack.peek().unwrap()(10) == 20);
+        assert!(test_list_stack.pop().unwrap()(10) == 20);
+        assert!(test_list_stack.pop().unwrap()(10) == 11);
+    }
+
+    #[test]
+    fn vec_stack_iter() {
+        let mut test_stack: VecStack<i32> = [10, 42, 59492957].into_iter().collect();
+
+        assert!(test_stack.iter().copied().collect::<Vec<_>>() == vec![59492957, 42, 10]);
+        for data in test_stack.iter_mut() {
+            *data += 1;
+        }
+        assert!((&test_stack).into_iter().count() == 3);
+        assert!(test_stack.count() == 3);
+        assert!(test_stack.into_iter().collect::<Vec<_>>() == vec![59492958, 43, 11]);
+    }
+
+    #[test]
+    fn vec_stack_drain_and_extend() {
+        let mut test_stack = VecStack::new();
+
+        test_stack.push_unbounded(10);
+        test_stack.extend([42, 59492957]);
+        assert!(*test_stack.peek().unwrap() == 59492957);
+        assert!(test_stack.drain().collect::<Vec<_>>() == vec![59492957, 42, 10]);
+        assert!(test_stack.is_empty());
+        assert!(test_stack.pop().is_none());
+    }
+
+    #[test]
+    fn list_stack_iter() {
+        let mut test_stack: ListStack<i32> = [10, 42, 59492957].into_iter().collect();
+
+        assert!(test_stack.iter().size_hint() == (3, Some(3)));
+        assert!(test_stack.iter().copied().collect::<Vec<_>>() == vec![59492957, 42, 10]);
+        for data in test_stack.iter_mut() {
+            *data += 1;
+        }
+        assert!((&test_stack).into_iter().count() == 3);
+        assert!(test_stack.count() == 3);
+        assert!(test_stack.into_iter().collect::<Vec<_>>() == vec![59492958, 43, 11]);
+    }
+
+    #[test]
+    fn list_stack_drain_and_extend() {
+        let mut test_stack = ListStack::new();
+
+        test_stack.push_unbounded(10);
+        test_stack.extend([42, 59492957]);
+        assert!(*test_stack.peek().unwrap() == 59492957);
+        assert!(test_stack.drain().next() == Some(59492957));
+        assert!(test_stack.is_empty());
+        assert!(test_stack.pop().is_none());
+    }
+
+    #[test]
+    fn list_stack_clone_and_debug() {
+        let test_stack: ListStack<i32> = [10, 42, 59492957].into_iter().collect();
+        let mut test_clone = test_stack.clone();
+
+        assert!(format!("{:?}", test_stack) == "[59492957, 42, 10]");
+        assert!(test_clone.count() == 3);
+        assert!(test_clone.pop().unwrap() == 59492957);
+        assert!(test_clone.pop().unwrap() == 42);
+        assert!(test_clone.pop().unwrap() == 10);
+        assert!(test_clone.pop().is_none());
+        assert!(test_stack.count() == 3);
+    }
+
+    const LONG_CHAIN_LENGTH: usize = 10_000_000;
+
+    struct CountingWriter(usize);
+
+    impl fmt::Write for CountingWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn list_stack_drop_long_chain() {
+        let test_stack: ListStack<usize> = (0..LONG_CHAIN_LENGTH).collect();
+
+        assert!(test_stack.count() == LONG_CHAIN_LENGTH);
+        drop(test_stack);
+    }
+
+    #[test]
+    fn list_stack_clone_long_chain() {
+        let test_stack: ListStack<usize> = (0..LONG_CHAIN_LENGTH).collect();
+        let test_clone = test_stack.clone();
+
+        assert!(test_clone.count() == LONG_CHAIN_LENGTH);
+        assert!(*test_clone.peek().unwrap() == LONG_CHAIN_LENGTH - 1);
+        assert!(test_clone.iter().last() == Some(&0));
+    }
+
+    #[test]
+    fn list_stack_debug_long_chain() {
+        let test_stack: ListStack<u8> = std::iter::repeat_n(0, LONG_CHAIN_LENGTH).collect();
+        let mut test_writer = CountingWriter(0);
+
+        fmt::write(&mut test_writer, format_args!("{:?}", test_stack)).unwrap();
+        assert!(test_writer.0 == LONG_CHAIN_LENGTH * 3);
+    }
+
+    #[test]
+    fn array_stack_push_and_pop() {
+        let mut test_stack: ArrayStack<i32, 3> = ArrayStack::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
+
+        assert!(test_stack.capacity() == 3);
+        assert!(test_stack.push(test_data_1).is_ok());
+        assert!(test_stack.push(test_data_2).is_ok());
+        assert!(test_stack.push(test_data_3).is_ok());
+        assert!(test_stack.is_full());
+        assert!(test_stack.push(test_data_3) == Err(Error::CapacityExceeded));
+        assert!(test_stack.count() == 3);
+
+        assert!(*test_stack.peek().unwrap() == test_data_3);
+        assert!(test_stack.pop().unwrap() == test_data_3);
+        *test_stack.peek_mut().unwrap() += 1;
+        assert!(test_stack.pop().unwrap() == test_data_2 + 1);
+        assert!(test_stack.pop().unwrap() == test_data_1);
+        assert!(test_stack.is_empty());
+        assert!(test_stack.pop().is_none());
+        assert!(test_stack.peek().is_none());
+    }
+
+    #[test]
+    fn array_stack_push_when_full_through_trait() {
+        fn push_all<S: Stack<i32>>(stack: &mut S, data: &[i32]) -> Result<(), Error> {
+            for data in data {
+                stack.push(*data)?;
+            }
+            Ok(())
+        }
+        let mut test_stack: ArrayStack<i32, 2> = ArrayStack::new();
+
+        assert!(push_all(&mut test_stack, &[10, 42, 59492957]) == Err(Error::CapacityExceeded));
+        assert!(test_stack.count() == 2);
+        assert!(*test_stack.peek().unwrap() == 42);
+    }
+
+    #[test]
+    fn array_stack_drops_remaining_data() {
+        let test_data = std::rc::Rc::new(());
+        let mut test_stack: ArrayStack<std::rc::Rc<()>, 4> = ArrayStack::new();
+
+        for _ in 0..3 {
+            assert!(test_stack.push(test_data.clone()).is_ok());
+        }
+        drop(test_stack.pop());
+        assert!(std::rc::Rc::strong_count(&test_data) == 3);
+        drop(test_stack);
+        assert!(std::rc::Rc::strong_count(&test_data) == 1);
+    }
+
+    #[test]
+    fn aggregate_stack_sum_and_gcd() {
+        fn gcd(a: &u64, b: &u64) -> u64 {
+            let (mut a, mut b) = (*a, *b);
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        }
+        let mut test_sum_stack = AggregateStack::new(|a: &i64, b: &i64| a + b);
+        let mut test_gcd_stack = AggregateStack::new(gcd);
+
+        assert!(test_sum_stack.aggregate().is_none());
+        test_sum_stack.push(10);
+        test_sum_stack.push(-3);
+        test_sum_stack.push(42);
+        assert!(*test_sum_stack.aggregate().unwrap() == 49);
+        assert!(test_sum_stack.pop().unwrap() == 42);
+        assert!(*test_sum_stack.aggregate().unwrap() == 7);
+        assert!(test_sum_stack.count() == 2);
+
+        test_gcd_stack.push(48);
+        test_gcd_stack.push(36);
+        assert!(*test_gcd_stack.aggregate().unwrap() == 12);
+        test_gcd_stack.push(10);
+        assert!(*test_gcd_stack.aggregate().unwrap() == 2);
+        assert!(*test_gcd_stack.peek().unwrap() == 10);
+        test_gcd_stack.pop();
+        assert!(*test_gcd_stack.aggregate().unwrap() == 12);
+    }
+
+    #[test]
+    fn min_stack() {
+        let mut test_stack = MinStack::new();
+
+        assert!(test_stack.min().is_none());
+        test_stack.push(42);
+        test_stack.push(10);
+        test_stack.push(59492957);
+        test_stack.push(10);
+        assert!(*test_stack.min().unwrap() == 10);
+        assert!(test_stack.pop().unwrap() == 10);
+        assert!(*test_stack.min().unwrap() == 10);
+        assert!(test_stack.pop().unwrap() == 59492957);
+        assert!(test_stack.pop().unwrap() == 10);
+        assert!(*test_stack.min().unwrap() == 42);
+        assert!(test_stack.pop().unwrap() == 42);
+        assert!(test_stack.min().is_none());
+        assert!(test_stack.is_empty());
+    }
+
+    #[test]
+    fn max_stack() {
+        let mut test_stack = MaxStack::new();
+
+        assert!(test_stack.max().is_none());
+        test_stack.push(42);
+        test_stack.push(59492957);
+        test_stack.push(10);
+        assert!(*test_stack.max().unwrap() == 59492957);
+        assert!(*test_stack.peek().unwrap() == 10);
+        assert!(test_stack.pop().unwrap() == 10);
+        assert!(test_stack.pop().unwrap() == 59492957);
+        assert!(*test_stack.max().unwrap() == 42);
+        assert!(test_stack.count() == 1);
+    }
+
+    #[test]
+    fn persistent_stack_versions() {
+        let test_empty = PersistentStack::new();
+        let test_version_1 = test_empty.push(10);
+        let test_version_2 = test_version_1.push(42);
+        let test_version_3 = test_version_2.push(59492957);
+        let test_branch = test_version_1.push(-2222);
+
+        assert!(test_empty.is_empty());
+        assert!(test_empty.pop().is_none());
+        assert!(test_empty.peek().is_none());
+        assert!(*test_version_1.peek().unwrap() == 10);
+        assert!(*test_version_2.peek().unwrap() == 42);
+        assert!(*test_version_3.peek().unwrap() == 59492957);
+        assert!(test_version_3.count() == 3);
+        assert!(test_branch.iter().copied().collect::<Vec<_>>() == vec![-2222, 10]);
+
+        let test_popped = test_version_3.pop().unwrap();
+        assert!(*test_popped.peek().unwrap() == 42);
+        assert!(test_popped.count() == 2);
+        assert!(test_version_3.count() == 3);
+        assert!(format!("{:?}", test_version_3) == "[59492957, 42, 10]");
+    }
+
+    #[test]
+    fn persistent_stack_shares_nodes() {
+        let test_data = Rc::new(());
+        let test_base: PersistentStack<Rc<()>> = PersistentStack::new().push(test_data.clone());
+        let test_version_1 = test_base.push(test_data.clone());
+        let test_version_2 = test_base.clone();
+
+        assert!(Rc::strong_count(&test_data) == 3);
+        drop(test_base);
+        drop(test_version_1);
+        assert!(Rc::strong_count(&test_data) == 2);
+        assert!(test_version_2.count() == 1);
+        drop(test_version_2);
+        assert!(Rc::strong_count(&test_data) == 1);
+    }
+
+    #[test]
+    fn persistent_stack_drop_long_chain() {
+        let test_stack: PersistentStack<usize> = (0..LONG_CHAIN_LENGTH).collect();
+        let test_branch = test_stack.pop().unwrap();
+
+        assert!(test_stack.count() == LONG_CHAIN_LENGTH);
+        drop(test_stack);
+        assert!(test_branch.count() == LONG_CHAIN_LENGTH - 1);
+        drop(test_branch);
+    }
+
+    #[test]
+    fn concurrent_stack_single_thread() {
+        let test_stack = ConcurrentStack::new();
+        let (test_data_1, test_data_2, test_data_3) = (10, 42, 59492957);
 
-        assert!(test_stack.peek().unwrap() == test_data_3);
+        assert!(test_stack.is_empty());
+        assert!(test_stack.pop().is_none());
+        test_stack.push(test_data_1);
+        test_stack.push(test_data_2);
+        test_stack.push(test_data_3);
+        assert!(test_stack.count() == 3);
         assert!(test_stack.pop().unwrap() == test_data_3);
-        assert!(test_stack.peek().unwrap() == test_data_2);
         assert!(test_stack.pop().unwrap() == test_data_2);
-        assert!(test_stack.peek().unwrap() == test_data_1);
         assert!(test_stack.pop().unwrap() == test_data_1);
+        assert!(test_stack.pop().is_none());
+        assert!(test_stack.count() == 0);
+    }
+
+    #[test]
+    fn concurrent_stack_drops_remaining_data() {
+        let test_data = std::sync::Arc::new(());
+        let test_stack = ConcurrentStack::new();
+
+        for _ in 0..200 {
+            test_stack.push(test_data.clone());
+        }
+        for _ in 0..100 {
+            drop(test_stack.pop());
+        }
+        assert!(std::sync::Arc::strong_count(&test_data) == 101);
+        drop(test_stack);
+        assert!(std::sync::Arc::strong_count(&test_data) == 1);
+    }
+
+    const STRESS_THREADS: usize = 8;
+    const STRESS_ITEMS_PER_THREAD: usize = 20_000;
+
+    #[test]
+    fn concurrent_stack_stress_no_lost_or_duplicated_data() {
+        let test_stack = ConcurrentStack::new();
+        let popped: Vec<Vec<usize>> = std::thread::scope(|scope| {
+            for thread in 0..STRESS_THREADS {
+                let test_stack = &test_stack;
+                scope.spawn(move || {
+                    for item in 0..STRESS_ITEMS_PER_THREAD {
+                        test_stack.push(thread * STRESS_ITEMS_PER_THREAD + item);
+                    }
+                });
+            }
+            let poppers: Vec<_> = (0..STRESS_THREADS)
+                .map(|_| {
+                    let test_stack = &test_stack;
+                    scope.spawn(move || {
+                        let mut popped = Vec::new();
+                        for _ in 0..STRESS_ITEMS_PER_THREAD {
+                            if let Some(data) = test_stack.pop() {
+                                popped.push(data);
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            poppers
+                .into_iter()
+                .map(|popper| popper.join().unwrap())
+                .collect()
+        });
+
+        let mut seen = vec![false; STRESS_THREADS * STRESS_ITEMS_PER_THREAD];
+        for data in popped
+            .into_iter()
+            .flatten()
+            .chain(std::iter::from_fn(|| test_stack.pop()))
+        {
+            assert!(!seen[data], "{} was popped twice", data);
+            seen[data] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen), "data was lost");
+        assert!(test_stack.count() == 0);
+    }
+
+    #[test]
+    fn concurrent_stack_stress_interleaved_push_pop() {
+        let test_stack = ConcurrentStack::new();
+        let popped_total = AtomicUsize::new(0);
+        let popped_sum = AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for thread in 0..STRESS_THREADS {
+                let (test_stack, popped_total, popped_sum) =
+                    (&test_stack, &popped_total, &popped_sum);
+                scope.spawn(move || {
+                    for item in 0..STRESS_ITEMS_PER_THREAD {
+                        test_stack.push(thread * STRESS_ITEMS_PER_THREAD + item);
+                        if let Some(data) = test_stack.pop() {
+                            popped_total.fetch_add(1, Ordering::Relaxed);
+                            popped_sum.fetch_add(data, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        let total_items = STRESS_THREADS * STRESS_ITEMS_PER_THREAD;
+        assert!(popped_total.load(Ordering::Relaxed) == total_items);
+        assert!(popped_sum.load(Ordering::Relaxed) == total_items * (total_items - 1) / 2);
+        assert!(test_stack.is_empty());
+    }
+
+    #[test]
+    fn bounded_stack_reject() {
+        let mut test_stack = BoundedStack::new(2, OverflowPolicy::Reject);
+
+        assert!(test_stack.push(10).is_ok());
+        assert!(test_stack.push(42).is_ok());
+        assert!(test_stack.is_full());
+        assert!(test_stack.push(59492957) == Err(Error::CapacityExceeded));
+        assert!(test_stack.push(59492957) == Err(Error::CapacityExceeded));
+        assert!(test_stack.overflow_count() == 2);
+        assert!(test_stack.pop().unwrap() == 42);
+        assert!(test_stack.push(59492957).is_ok());
+        assert!(test_stack.pop().unwrap() == 59492957);
+        assert!(test_stack.pop().unwrap() == 10);
+        assert!(test_stack.pop().is_none());
+    }
+
+    #[test]
+    fn bounded_stack_drop_oldest() {
+        let mut test_stack = BoundedStack::new(3, OverflowPolicy::DropOldest);
+
+        for data in 0..5 {
+            assert!(test_stack.push(data).is_ok());
+        }
+        assert!(test_stack.count() == 3);
+        assert!(test_stack.overflow_count() == 2);
+        assert!(*test_stack.peek().unwrap() == 4);
+        assert!(test_stack.pop().unwrap() == 4);
+        assert!(test_stack.pop().unwrap() == 3);
+        assert!(test_stack.pop().unwrap() == 2);
+        assert!(test_stack.is_empty());
+    }
+
+    #[test]
+    fn bounded_stack_through_trait() {
+        fn push_all<S: Stack<i32>>(stack: &mut S, data: &[i32]) -> Result<(), Error> {
+            for data in data {
+                stack.push(*data)?;
+            }
+            Ok(())
+        }
+        let mut test_reject_stack = BoundedStack::new(2, OverflowPolicy::Reject);
+        let mut test_drop_stack = BoundedStack::new(2, OverflowPolicy::DropOldest);
+
+        assert!(
+            push_all(&mut test_reject_stack, &[10, 42, 59492957]) == Err(Error::CapacityExceeded)
+        );
+        assert!(test_reject_stack.count() == 2);
+        assert!(push_all(&mut test_drop_stack, &[10, 42, 59492957]).is_ok());
+        *test_drop_stack.peek_mut().unwrap() += 1;
+        assert!(test_drop_stack.pop().unwrap() == 59492958);
+        assert!(test_drop_stack.pop().unwrap() == 42);
+        assert!(test_drop_stack.pop().is_none());
+    }
+
+    #[test]
+    fn bounded_stack_block_rejects_without_threads() {
+        let mut test_stack = BoundedStack::new(1, OverflowPolicy::Block);
+
+        assert!(test_stack.push(10).is_ok());
+        assert!(test_stack.push(42) == Err(Error::CapacityExceeded));
+        assert!(test_stack.overflow_count() == 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn sync_bounded_stack_block() {
+        let test_stack = SyncBoundedStack::new(2, OverflowPolicy::Block);
+        let popped = std::sync::Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for data in 0..100 {
+                    assert!(test_stack.push(data).is_ok());
+                }
+            });
+            scope.spawn(|| {
+                while popped.lock().unwrap().len() < 100 {
+                    if let Some(data) = test_stack.pop() {
+                        popped.lock().unwrap().push(data);
+                    }
+                }
+            });
+        });
+
+        let mut popped = popped.into_inner().unwrap();
+        popped.sort_unstable();
+        assert!(popped == (0..100).collect::<Vec<_>>());
         assert!(test_stack.is_empty());
-        assert!(test_stack.peek().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn sync_bounded_stack_reject() {
+        let test_stack = SyncBoundedStack::new(1, OverflowPolicy::Reject);
+
+        assert!(test_stack.push(10).is_ok());
+        assert!(test_stack.push(42) == Err(Error::CapacityExceeded));
+        assert!(test_stack.overflow_count() == 1);
+        assert!(test_stack.count() == 1);
+        assert!(test_stack.pop().unwrap() == 10);
     }
 }
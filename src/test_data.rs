@@ -0,0 +1,21 @@
+use alloc::vec::Vec;
+
+/// A fixed-seed linear congruential generator, so the randomised tests see the
+/// same data on every run.
+pub(crate) struct PseudoRandom(u64);
+
+impl PseudoRandom {
+    pub(crate) fn new() -> Self {
+        PseudoRandom(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub(crate) fn next_below(&mut self, modulus: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+        (self.0 >> 33) % modulus
+    }
+}
+
+pub(crate) fn pseudo_random_data(length: usize, modulus: u64) -> Vec<u64> {
+    let mut random = PseudoRandom::new();
+    (0..length).map(|_| random.next_below(modulus)).collect()
+}
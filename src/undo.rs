@@ -0,0 +1,344 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::queue::{Deque, Queue, RingQueue};
+use crate::stack::{Stack, UnboundedStack, VecStack};
+
+pub trait Command<S> {
+    fn apply(&mut self, state: &mut S);
+
+    fn undo(&mut self, state: &mut S);
+
+    /// Folds an already applied `next` command into `self`, returning false if
+    /// the two can not be combined.
+    fn merge(&mut self, _next: &Self) -> bool {
+        false
+    }
+}
+
+/// Unlike the usual two-stack design, only the redo entries are kept on a
+/// stack. The undo entries sit in a `RingQueue`, newest at the back, because a
+/// history capped by `with_max_depth` has to drop its oldest entry on every
+/// push once full, and a ring does that in O(1) where a stack would have to
+/// shift every entry above the bottom one.
+pub struct UndoHistory<C> {
+    undo_ring: RingQueue<Vec<C>>,
+    redo_stack: VecStack<Vec<C>>,
+    transaction: Vec<C>,
+    transaction_depth: usize,
+    max_depth: Option<usize>,
+    merging: bool,
+}
+
+impl<C> UndoHistory<C> {
+    pub fn new() -> Self {
+        UndoHistory {
+            undo_ring: RingQueue::new(),
+            redo_stack: VecStack::new(),
+            transaction: Vec::new(),
+            transaction_depth: 0,
+            max_depth: None,
+            merging: false,
+        }
+    }
+
+    /// Keeps at most `max_depth` undo entries, dropping the oldest first.
+    pub fn with_max_depth(max_depth: usize) -> Self {
+        let mut history = UndoHistory::new();
+        history.max_depth = Some(max_depth);
+        history
+    }
+
+    pub fn set_merging(&mut self, merging: bool) {
+        self.merging = merging;
+    }
+
+    pub fn apply<S>(&mut self, mut command: C, state: &mut S)
+    where
+        C: Command<S>,
+    {
+        command.apply(state);
+        self.redo_stack = VecStack::new();
+
+        if self.transaction_depth > 0 {
+            if !(self.merging && Self::merge_into(self.transaction.last_mut(), &command)) {
+                self.transaction.push(command);
+            }
+            return;
+        }
+
+        let top_entry = self
+            .undo_ring
+            .count()
+            .checked_sub(1)
+            .and_then(|top| self.undo_ring.get_mut(top))
+            .filter(|entry| entry.len() == 1);
+        if self.merging && Self::merge_into(top_entry.and_then(|entry| entry.last_mut()), &command)
+        {
+            return;
+        }
+        self.push_entry(vec![command]);
+    }
+
+    /// Any open transaction is committed before the undo takes place.
+    pub fn undo<S>(&mut self, state: &mut S) -> Result<(), Error>
+    where
+        C: Command<S>,
+    {
+        self.close_transactions();
+        let mut entry = self.undo_ring.pop_back().ok_or(Error::Empty)?;
+        for command in entry.iter_mut().rev() {
+            command.undo(state);
+        }
+        self.redo_stack.push_unbounded(entry);
+        Ok(())
+    }
+
+    pub fn redo<S>(&mut self, state: &mut S) -> Result<(), Error>
+    where
+        C: Command<S>,
+    {
+        self.close_transactions();
+        let mut entry = self.redo_stack.pop().ok_or(Error::Empty)?;
+        for command in entry.iter_mut() {
+            command.apply(state);
+        }
+        self.undo_ring.push_back(entry);
+        Ok(())
+    }
+
+    /// Groups every command applied until the matching `commit_transaction`
+    /// into a single undo entry. Transactions may be nested.
+    pub fn begin_transaction(&mut self) {
+        self.transaction_depth += 1;
+    }
+
+    pub fn commit_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            return;
+        }
+        self.transaction_depth -= 1;
+        if self.transaction_depth == 0 && !self.transaction.is_empty() {
+            let entry = core::mem::take(&mut self.transaction);
+            self.push_entry(entry);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_ring.is_empty() || !self.transaction.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo_ring.count()
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo_stack.count()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_ring = RingQueue::new();
+        self.redo_stack = VecStack::new();
+        self.transaction = Vec::new();
+        self.transaction_depth = 0;
+    }
+
+    fn merge_into<S>(previous: Option<&mut C>, command: &C) -> bool
+    where
+        C: Command<S>,
+    {
+        previous.is_some_and(|previous| previous.merge(command))
+    }
+
+    fn close_transactions(&mut self) {
+        if self.transaction_depth > 0 {
+            self.transaction_depth = 1;
+            self.commit_transaction();
+        }
+    }
+
+    fn push_entry(&mut self, entry: Vec<C>) {
+        self.undo_ring.push_back(entry);
+        if let Some(max_depth) = self.max_depth {
+            while self.undo_ring.count() > max_depth {
+                self.undo_ring.pop_front();
+            }
+        }
+    }
+}
+
+impl<C> Default for UndoHistory<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Edit {
+        Insert(usize, String),
+        Delete(usize, String),
+    }
+
+    impl Command<String> for Edit {
+        fn apply(&mut self, state: &mut String) {
+            match self {
+                Edit::Insert(position, text) => state.insert_str(*position, text),
+                Edit::Delete(position, text) => {
+                    state.replace_range(*position..*position + text.len(), "")
+                }
+            }
+        }
+
+        fn undo(&mut self, state: &mut String) {
+            match self {
+                Edit::Insert(position, text) => {
+                    state.replace_range(*position..*position + text.len(), "")
+                }
+                Edit::Delete(position, text) => state.insert_str(*position, text),
+            }
+        }
+
+        fn merge(&mut self, next: &Self) -> bool {
+            match (self, next) {
+                (Edit::Insert(position, text), Edit::Insert(next_position, next_text))
+                    if *position + text.len() == *next_position =>
+                {
+                    text.push_str(next_text);
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    fn insert(position: usize, text: &str) -> Edit {
+        Edit::Insert(position, text.to_string())
+    }
+
+    fn delete(position: usize, text: &str) -> Edit {
+        Edit::Delete(position, text.to_string())
+    }
+
+    #[test]
+    fn undo_history_apply_undo_redo() {
+        let mut test_history = UndoHistory::new();
+        let mut test_state = String::new();
+
+        test_history.apply(insert(0, "hello"), &mut test_state);
+        test_history.apply(insert(5, " world"), &mut test_state);
+        test_history.apply(delete(0, "hello"), &mut test_state);
+        assert!(test_state == " world");
+        assert!(test_history.undo_count() == 3);
+
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state == "hello world");
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state == "hello");
+        assert!(test_history.redo(&mut test_state).is_ok());
+        assert!(test_state == "hello world");
+        assert!(test_history.undo_count() == 2);
+        assert!(test_history.redo_count() == 1);
+
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state.is_empty());
+        assert!(test_history.undo(&mut test_state) == Err(Error::Empty));
+        assert!(!test_history.can_undo());
+    }
+
+    #[test]
+    fn undo_history_apply_clears_redo() {
+        let mut test_history = UndoHistory::new();
+        let mut test_state = String::new();
+
+        test_history.apply(insert(0, "a"), &mut test_state);
+        test_history.apply(insert(1, "b"), &mut test_state);
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_history.can_redo());
+
+        test_history.apply(insert(1, "c"), &mut test_state);
+        assert!(!test_history.can_redo());
+        assert!(test_history.redo(&mut test_state) == Err(Error::Empty));
+        assert!(test_state == "ac");
+    }
+
+    #[test]
+    fn undo_history_transactions() {
+        let mut test_history = UndoHistory::new();
+        let mut test_state = String::new();
+
+        test_history.apply(insert(0, "x"), &mut test_state);
+        test_history.begin_transaction();
+        test_history.apply(insert(1, "a"), &mut test_state);
+        test_history.begin_transaction();
+        test_history.apply(insert(2, "b"), &mut test_state);
+        test_history.commit_transaction();
+        test_history.apply(delete(0, "x"), &mut test_state);
+        test_history.commit_transaction();
+        assert!(test_state == "ab");
+        assert!(test_history.undo_count() == 2);
+
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state == "x");
+        assert!(test_history.redo(&mut test_state).is_ok());
+        assert!(test_state == "ab");
+    }
+
+    #[test]
+    fn undo_history_undo_commits_open_transaction() {
+        let mut test_history = UndoHistory::new();
+        let mut test_state = String::new();
+
+        test_history.begin_transaction();
+        test_history.apply(insert(0, "a"), &mut test_state);
+        test_history.apply(insert(1, "b"), &mut test_state);
+        assert!(test_history.can_undo());
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state.is_empty());
+        assert!(test_history.redo(&mut test_state).is_ok());
+        assert!(test_state == "ab");
+    }
+
+    #[test]
+    fn undo_history_max_depth() {
+        let mut test_history = UndoHistory::with_max_depth(2);
+        let mut test_state = String::new();
+
+        test_history.apply(insert(0, "a"), &mut test_state);
+        test_history.apply(insert(1, "b"), &mut test_state);
+        test_history.apply(insert(2, "c"), &mut test_state);
+        assert!(test_history.undo_count() == 2);
+
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_history.undo(&mut test_state) == Err(Error::Empty));
+        assert!(test_state == "a");
+    }
+
+    #[test]
+    fn undo_history_merging() {
+        let mut test_history = UndoHistory::new();
+        let mut test_state = String::new();
+
+        test_history.set_merging(true);
+        test_history.apply(insert(0, "h"), &mut test_state);
+        test_history.apply(insert(1, "i"), &mut test_state);
+        test_history.apply(insert(2, "!"), &mut test_state);
+        test_history.apply(insert(0, ">"), &mut test_state);
+        assert!(test_state == ">hi!");
+        assert!(test_history.undo_count() == 2);
+
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state == "hi!");
+        assert!(test_history.undo(&mut test_state).is_ok());
+        assert!(test_state.is_empty());
+    }
+}